@@ -1,3 +1,5 @@
+use core::convert::TryFrom;
+
 #[derive(Debug)]
 pub enum N2kError {
     InvalidPayloadSize {
@@ -6,4 +8,1067 @@ pub enum N2kError {
         actual: usize,
     },
     UnknownPgn(u32),
+    /// Value cannot be represented in a field
+    OutOfRange,
+    /// Text is not in the format of the value
+    InvalidFormat,
+    /// Buffer cannot hold the payload
+    BufferTooSmall {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+/// Strips the padding of a fixed length string, unused bytes are filled with 0xff, 0x00, '@'
+/// or ' ' depending on the sender
+#[inline(always)]
+pub fn trim_padding(bytes: &[u8]) -> &[u8] {
+    let end = bytes
+        .iter()
+        .rposition(|b| !matches!(b, 0x00 | 0xff | b'@' | b' '))
+        .map_or(0, |i| i + 1);
+    &bytes[..end]
+}
+
+/// Offsets `value` by a half away from zero, so the truncating `as` cast that follows rounds
+/// to the nearest integer
+#[inline(always)]
+pub fn round(value: f32) -> f32 {
+    if value < 0.0 {
+        value - 0.5
+    } else {
+        value + 0.5
+    }
+}
+
+/// `round` for `f64` values
+#[inline(always)]
+pub fn round_f64(value: f64) -> f64 {
+    if value < 0.0 {
+        value - 0.5
+    } else {
+        value + 0.5
+    }
+}
+
+/// Largest payload kept for PGNs no variant matched, as long as a fast packet
+pub const MAX_RAW_PGN_SIZE: usize = 223;
+
+/// Payload of a PGN none of the generated variants matched, truncated to
+/// `MAX_RAW_PGN_SIZE`
+#[derive(Clone)]
+pub struct RawPgn {
+    pgn: u32,
+    len: usize,
+    data: [u8; MAX_RAW_PGN_SIZE],
+}
+
+impl RawPgn {
+    pub fn new(pgn: u32, payload: &[u8]) -> Self {
+        let len = payload.len().min(MAX_RAW_PGN_SIZE);
+        let mut data = [0; MAX_RAW_PGN_SIZE];
+        data[..len].copy_from_slice(&payload[..len]);
+        RawPgn { pgn, len, data }
+    }
+
+    pub fn pgn(&self) -> u32 {
+        self.pgn
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+impl core::fmt::Debug for RawPgn {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RawPgn")
+            .field("pgn", &self.pgn)
+            .field("data", &self.data())
+            .finish()
+    }
+}
+
+/// Group of fields repeated at the end of a PGN
+pub trait RepeatingGroup<'m> {
+    /// Bits taken by one group
+    const BIT_LENGTH: usize;
+
+    /// Group starting at bit `offset` of `raw`
+    fn at(raw: &'m [u8], offset: usize) -> Self;
+}
+
+/// Iterator over the repeating groups of a message, ends after the announced number of
+/// groups or at the end of the payload
+pub struct Repeated<'m, G> {
+    raw: &'m [u8],
+    offset: usize,
+    remaining: usize,
+    group: core::marker::PhantomData<G>,
+}
+
+impl<'m, G> Repeated<'m, G> {
+    pub fn new(raw: &'m [u8], offset: usize, count: usize) -> Self {
+        Repeated {
+            raw,
+            offset,
+            remaining: count,
+            group: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<'m, G> Clone for Repeated<'m, G> {
+    fn clone(&self) -> Self {
+        Repeated::new(self.raw, self.offset, self.remaining)
+    }
+}
+
+impl<'m, G: RepeatingGroup<'m>> Iterator for Repeated<'m, G> {
+    type Item = G;
+
+    fn next(&mut self) -> Option<G> {
+        if self.remaining == 0 || self.offset + G::BIT_LENGTH > self.raw.len() * 8 {
+            return None;
+        }
+        let group = G::at(self.raw, self.offset);
+        self.offset += G::BIT_LENGTH;
+        self.remaining -= 1;
+        Some(group)
+    }
+}
+
+impl<'m, G: RepeatingGroup<'m> + core::fmt::Debug> core::fmt::Debug for Repeated<'m, G> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// Encodings of variable length strings, every field after one of them moves with its length
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StringEncoding {
+    /// Total length byte, control byte (0 UTF-16, 1 ASCII) and the characters
+    Lau,
+    /// Length byte and the characters
+    Lz,
+    /// Characters between a 0x02 start and a 0x01 stop byte
+    StartStop,
+}
+
+impl StringEncoding {
+    /// Encoding of an empty string
+    pub fn empty(self) -> &'static [u8] {
+        match self {
+            StringEncoding::Lau => &[0x02, 0x01],
+            StringEncoding::Lz => &[0x00],
+            StringEncoding::StartStop => &[0x02, 0x01],
+        }
+    }
+
+    /// Bytes taken by the field at the start of `data`, None if it was not received completely
+    pub fn field_len(self, data: &[u8]) -> Option<usize> {
+        let len = match self {
+            StringEncoding::Lau => (*data.first()? as usize).max(2),
+            StringEncoding::Lz => *data.first()? as usize + 1,
+            StringEncoding::StartStop => match *data.first()? {
+                0x02 => data.iter().position(|b| *b == 0x01)? + 1,
+                // some senders use a total length byte instead
+                len if len > 0x02 => len as usize,
+                _ => 1,
+            },
+        };
+        if len > data.len() {
+            return None;
+        }
+        Some(len)
+    }
+
+    /// String in `field`, which is exactly `field_len` bytes long
+    pub fn decode(self, field: &[u8]) -> Option<N2kString<'_>> {
+        Some(match self {
+            StringEncoding::Lau => match *field.get(1)? {
+                0 => N2kString::Utf16(&field[2..]),
+                _ => N2kString::Ascii(&field[2..]),
+            },
+            StringEncoding::Lz => N2kString::Ascii(&field[1..]),
+            StringEncoding::StartStop => match field[0] {
+                0x02 => N2kString::Ascii(&field[1..field.len() - 1]),
+                len if len > 0x02 && field.get(1) == Some(&0x01) => N2kString::Ascii(&field[2..]),
+                len if len > 0x02 => N2kString::Ascii(&field[1..]),
+                _ => N2kString::Ascii(&[]),
+            },
+        })
+    }
+
+    /// Encodes `value` into `buf` and returns the bytes written. Characters that do not fit
+    /// into `buf` or the length byte are left out.
+    pub fn encode(self, value: &str, buf: &mut [u8]) -> usize {
+        let max = buf.len().min(255);
+        match self {
+            StringEncoding::Lau if value.is_ascii() => {
+                let len = value.len().min(max - 2);
+                buf[2..2 + len].copy_from_slice(&value.as_bytes()[..len]);
+                buf[0] = (len + 2) as u8;
+                buf[1] = 0x01;
+                len + 2
+            }
+            StringEncoding::Lau => {
+                let mut len = 2;
+                for unit in value.encode_utf16() {
+                    if len + 2 > max {
+                        break;
+                    }
+                    buf[len..len + 2].copy_from_slice(&unit.to_le_bytes());
+                    len += 2;
+                }
+                buf[0] = len as u8;
+                buf[1] = 0x00;
+                len
+            }
+            StringEncoding::Lz => {
+                let len = value.len().min(max - 1);
+                buf[1..1 + len].copy_from_slice(&value.as_bytes()[..len]);
+                buf[0] = len as u8;
+                len + 1
+            }
+            StringEncoding::StartStop => {
+                let len = value.len().min(max - 2);
+                buf[0] = 0x02;
+                buf[1..1 + len].copy_from_slice(&value.as_bytes()[..len]);
+                buf[1 + len] = 0x01;
+                len + 2
+            }
+        }
+    }
+}
+
+/// Replaces the `old` bytes at `offset` of `raw[..*len]` with `new`, moving what follows. The
+/// payload is cut off at the end of `raw`.
+pub fn replace_field(raw: &mut [u8], len: &mut usize, offset: usize, old: usize, new: &[u8]) {
+    let end = (*len).max(offset + old);
+    let tail = end - offset - old;
+    let new_len = (offset + new.len()).min(raw.len());
+    let moved = tail.min(raw.len() - new_len);
+    raw.copy_within(offset + old..offset + old + moved, new_len);
+    raw[offset..new_len].copy_from_slice(&new[..new_len - offset]);
+    *len = new_len + moved;
+    // bytes freed by a shorter field read as "not available" again
+    if *len < end {
+        raw[*len..end].fill(0xff);
+    }
+}
+
+/// Text of a variable length string field
+#[derive(Clone, Copy, PartialEq)]
+pub enum N2kString<'a> {
+    Ascii(&'a [u8]),
+    /// Little endian UTF-16 code units
+    Utf16(&'a [u8]),
+}
+
+impl<'a> N2kString<'a> {
+    /// Text of an ASCII string, None if it is UTF-16 or not valid UTF-8
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            N2kString::Ascii(bytes) => core::str::from_utf8(trim_padding(bytes)).ok(),
+            N2kString::Utf16(_) => None,
+        }
+    }
+
+    /// Characters of the string, invalid ones are replaced
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        let (ascii, utf16): (&'a [u8], &'a [u8]) = match *self {
+            N2kString::Ascii(bytes) => (trim_padding(bytes), &[]),
+            N2kString::Utf16(bytes) => (&[], bytes),
+        };
+        let units = utf16
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|unit| *unit != 0);
+        ascii.iter().map(|b| *b as char).chain(
+            core::char::decode_utf16(units).map(|c| c.unwrap_or(core::char::REPLACEMENT_CHARACTER)),
+        )
+    }
+}
+
+impl<'a> core::fmt::Display for N2kString<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.as_str() {
+            Some(s) => f.write_str(s),
+            None => self.chars().try_for_each(|c| core::fmt::Write::write_char(f, c)),
+        }
+    }
+}
+
+impl<'a> core::fmt::Debug for N2kString<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+/// Value of a numeric field. NMEA 2000 reserves the highest raw values of fields, the maximum
+/// (all bits set for unsigned fields) for data not being available and the next two values down
+/// for it being out of range and reserved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum N2kValue<T> {
+    Value(T),
+    NotAvailable,
+    OutOfRange,
+    Reserved,
+}
+
+impl<T> N2kValue<T> {
+    /// The value, None if it is not available, out of range or reserved
+    pub fn value(self) -> Option<T> {
+        match self {
+            N2kValue::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn is_value(&self) -> bool {
+        matches!(self, N2kValue::Value(_))
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> N2kValue<U> {
+        match self {
+            N2kValue::Value(value) => N2kValue::Value(f(value)),
+            N2kValue::NotAvailable => N2kValue::NotAvailable,
+            N2kValue::OutOfRange => N2kValue::OutOfRange,
+            N2kValue::Reserved => N2kValue::Reserved,
+        }
+    }
+
+    /// Both values, or the first that is none
+    pub fn zip<U>(self, other: N2kValue<U>) -> N2kValue<(T, U)> {
+        match (self, other) {
+            (N2kValue::Value(value), N2kValue::Value(other)) => N2kValue::Value((value, other)),
+            (N2kValue::Value(_), other) => other.map(|_| unreachable!()),
+            (value, _) => value.map(|_| unreachable!()),
+        }
+    }
+}
+
+impl<T> From<T> for N2kValue<T> {
+    fn from(value: T) -> Self {
+        N2kValue::Value(value)
+    }
+}
+
+/// Position in degrees, north and east are positive
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeoPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl GeoPosition {
+    /// Mean radius of the earth in metres
+    pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+    pub const fn new(latitude: f64, longitude: f64) -> Self {
+        GeoPosition {
+            latitude,
+            longitude,
+        }
+    }
+
+    /// Great circle distance to `other` in metres, by the haversine formula
+    pub fn distance_to(&self, other: &GeoPosition) -> f64 {
+        let (latitude, other_latitude) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let delta_latitude = other_latitude - latitude;
+        let delta_longitude = (other.longitude - self.longitude).to_radians();
+        let haversine = (delta_latitude / 2.0).sin().powi(2)
+            + latitude.cos() * other_latitude.cos() * (delta_longitude / 2.0).sin().powi(2);
+        2.0 * Self::EARTH_RADIUS * haversine.sqrt().min(1.0).asin()
+    }
+
+    /// Initial bearing of the great circle to `other` in degrees clockwise from true north,
+    /// from 0 up to 360
+    pub fn bearing_to(&self, other: &GeoPosition) -> f64 {
+        let (latitude, other_latitude) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let delta_longitude = (other.longitude - self.longitude).to_radians();
+        let y = delta_longitude.sin() * other_latitude.cos();
+        let x = latitude.cos() * other_latitude.sin()
+            - latitude.sin() * other_latitude.cos() * delta_longitude.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+}
+
+/// Layout of a PGN as canboat describes it, for handling messages without knowing their types
+#[derive(Debug, Clone, Copy)]
+pub struct PgnDescriptor {
+    pub pgn: u32,
+    /// Id canboat gives the PGN, the message type is named after it
+    pub id: &'static str,
+    pub name: &'static str,
+    pub priority: n2k::Priority,
+    pub fast_packet: bool,
+    /// Length of the payload in bytes, the longest one of variable length PGNs is unknown
+    pub length: usize,
+    /// Number of fields at the end of `fields` that repeat
+    pub repeating_fields: usize,
+    pub fields: &'static [FieldDescriptor],
+}
+
+impl PgnDescriptor {
+    /// Field with the canboat id `id`
+    pub fn field(&self, id: &str) -> Option<&'static FieldDescriptor> {
+        self.fields.iter().find(|field| field.id == id)
+    }
+
+    /// Fields repeated at the end of the payload
+    pub fn repeating_group(&self) -> &'static [FieldDescriptor] {
+        &self.fields[self.fields.len() - self.repeating_fields..]
+    }
+}
+
+/// Layout of a field as canboat describes it
+#[derive(Debug, Clone, Copy)]
+pub struct FieldDescriptor {
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Type of the field in canboat, empty for plain numbers
+    pub kind: &'static str,
+    /// None for fields after a variable length string, which move with its length
+    pub bit_offset: Option<usize>,
+    pub bit_length: usize,
+    /// Whether the raw value is signed
+    pub signed: bool,
+    /// The value is the raw value plus `offset`, times `resolution`
+    pub resolution: f64,
+    pub offset: i64,
+    pub unit: Option<&'static str>,
+    /// Values of a lookup table and their names
+    pub lookup: &'static [(u32, &'static str)],
+    /// Bits of a bitfield and their names
+    pub bits: &'static [(u8, &'static str)],
+    /// Value the field has in this variant of the PGN
+    pub match_value: Option<u64>,
+}
+
+impl FieldDescriptor {
+    /// Name of the lookup table value `value`
+    pub fn lookup_name(&self, value: u32) -> Option<&'static str> {
+        self.lookup
+            .iter()
+            .find(|(v, _)| *v == value)
+            .map(|(_, name)| *name)
+    }
+}
+
+/// Date field, days since 1970-01-01
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct N2kDate {
+    days: u16,
+}
+
+impl N2kDate {
+    /// Days between 0000-03-01 and 1970-01-01, the proleptic Gregorian calendar is counted
+    /// from March so leap days come last
+    const UNIX_EPOCH_DAYS: i64 = 719_468;
+
+    pub const fn from_days(days: u16) -> Self {
+        N2kDate { days }
+    }
+
+    pub const fn days(self) -> u16 {
+        self.days
+    }
+
+    /// None for invalid dates and dates the field cannot hold
+    pub fn from_ymd(year: i32, month: u8, day: u8) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        let year = year as i64 - (month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month_from_march = (month as i64 + 9) % 12;
+        let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+        let day_of_era =
+            year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - Self::UNIX_EPOCH_DAYS;
+        u16::try_from(days).ok().map(Self::from_days)
+    }
+
+    /// Year, month and day of the month
+    pub fn ymd(self) -> (i32, u8, u8) {
+        let days = self.days as i64 + Self::UNIX_EPOCH_DAYS;
+        let era = days / 146_097;
+        let day_of_era = days - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+            - day_of_era / 146_096)
+            / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+        let month = (month_from_march + 2) % 12 + 1;
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+        (year as i32, month as u8, day as u8)
+    }
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl core::fmt::Display for N2kDate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl core::str::FromStr for N2kDate {
+    type Err = N2kError;
+
+    /// Parses the `YYYY-MM-DD` of `Display`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or(N2kError::InvalidFormat);
+        let year = next()?.parse().map_err(|_| N2kError::InvalidFormat)?;
+        let month = next()?.parse().map_err(|_| N2kError::InvalidFormat)?;
+        let day = next()?.parse().map_err(|_| N2kError::InvalidFormat)?;
+        N2kDate::from_ymd(year, month, day).ok_or(N2kError::OutOfRange)
+    }
+}
+
+/// Time field, 0.0001 s since midnight. Some PGNs use it for durations, which may exceed a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct N2kTime {
+    ticks: u32,
+}
+
+impl N2kTime {
+    pub const TICKS_PER_SECOND: u32 = 10_000;
+    const NANOS_PER_TICK: u32 = 100_000;
+
+    pub const fn from_ticks(ticks: u32) -> Self {
+        N2kTime { ticks }
+    }
+
+    pub const fn ticks(self) -> u32 {
+        self.ticks
+    }
+
+    /// None for times of day that do not exist, nanoseconds are truncated to ticks
+    pub fn from_hms_nano(hour: u8, minute: u8, second: u8, nano: u32) -> Option<Self> {
+        if hour > 23 || minute > 59 || second > 59 || nano >= 1_000_000_000 {
+            return None;
+        }
+        let seconds = (hour as u32 * 60 + minute as u32) * 60 + second as u32;
+        Some(Self::from_ticks(
+            seconds * Self::TICKS_PER_SECOND + nano / Self::NANOS_PER_TICK,
+        ))
+    }
+
+    /// Whole seconds since midnight
+    pub fn seconds(self) -> u32 {
+        self.ticks / Self::TICKS_PER_SECOND
+    }
+
+    /// Fraction of the second in nanoseconds
+    pub fn subsec_nanos(self) -> u32 {
+        self.ticks % Self::TICKS_PER_SECOND * Self::NANOS_PER_TICK
+    }
+
+    /// Hour, minute and second, None past the end of the day
+    pub fn hms(self) -> Option<(u8, u8, u8)> {
+        let seconds = self.seconds();
+        if seconds >= 24 * 60 * 60 {
+            return None;
+        }
+        Some((
+            (seconds / 3600) as u8,
+            (seconds / 60 % 60) as u8,
+            (seconds % 60) as u8,
+        ))
+    }
+}
+
+impl core::fmt::Display for N2kTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let seconds = self.seconds();
+        write!(
+            f,
+            "{:02}:{:02}:{:02}.{:04}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            self.ticks % Self::TICKS_PER_SECOND
+        )
+    }
+}
+
+impl core::str::FromStr for N2kTime {
+    type Err = N2kError;
+
+    /// Parses the `HH:MM:SS.ffff` of `Display`, the fraction is optional and hours may exceed
+    /// a day
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hms, fraction) = match s.find('.') {
+            Some(dot) => (&s[..dot], &s[dot + 1..]),
+            None => (s, ""),
+        };
+        let mut parts = hms.splitn(3, ':');
+        let mut next = || -> Result<u32, N2kError> {
+            parts
+                .next()
+                .and_then(|part| part.parse().ok())
+                .ok_or(N2kError::InvalidFormat)
+        };
+        let (hour, minute, second) = (next()?, next()?, next()?);
+        if minute > 59 || second > 59 || fraction.len() > 4 {
+            return Err(N2kError::InvalidFormat);
+        }
+        let ticks = match fraction {
+            "" => 0,
+            fraction => {
+                let ticks: u32 = fraction.parse().map_err(|_| N2kError::InvalidFormat)?;
+                ticks * 10u32.pow(4 - fraction.len() as u32)
+            }
+        };
+        hour.checked_mul(3600)
+            .and_then(|seconds| seconds.checked_add(minute * 60 + second))
+            .and_then(|seconds| seconds.checked_mul(N2kTime::TICKS_PER_SECOND))
+            .and_then(|seconds| seconds.checked_add(ticks))
+            .map(N2kTime::from_ticks)
+            .ok_or(N2kError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<N2kDate> for chrono::NaiveDate {
+    fn from(date: N2kDate) -> Self {
+        let (year, month, day) = date.ymd();
+        chrono::NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDate> for N2kDate {
+    type Error = N2kError;
+
+    fn try_from(date: chrono::NaiveDate) -> Result<Self, Self::Error> {
+        use chrono::Datelike;
+        N2kDate::from_ymd(date.year(), date.month() as u8, date.day() as u8)
+            .ok_or(N2kError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<N2kTime> for chrono::NaiveTime {
+    type Error = N2kError;
+
+    fn try_from(time: N2kTime) -> Result<Self, Self::Error> {
+        chrono::NaiveTime::from_num_seconds_from_midnight_opt(time.seconds(), time.subsec_nanos())
+            .ok_or(N2kError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveTime> for N2kTime {
+    /// Leap seconds end up in the following second
+    fn from(time: chrono::NaiveTime) -> Self {
+        use chrono::Timelike;
+        N2kTime::from_ticks(
+            time.num_seconds_from_midnight() * N2kTime::TICKS_PER_SECOND
+                + time.nanosecond() / N2kTime::NANOS_PER_TICK,
+        )
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<N2kDate> for time::Date {
+    fn from(date: N2kDate) -> Self {
+        // Julian day of 1970-01-01
+        time::Date::from_julian_day(2_440_588 + date.days() as i32).unwrap()
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<time::Date> for N2kDate {
+    type Error = N2kError;
+
+    fn try_from(date: time::Date) -> Result<Self, Self::Error> {
+        u16::try_from(date.to_julian_day() - 2_440_588)
+            .map(N2kDate::from_days)
+            .map_err(|_| N2kError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<N2kTime> for time::Time {
+    type Error = N2kError;
+
+    fn try_from(time: N2kTime) -> Result<Self, Self::Error> {
+        let (hour, minute, second) = time.hms().ok_or(N2kError::OutOfRange)?;
+        time::Time::from_hms_nano(hour, minute, second, time.subsec_nanos())
+            .map_err(|_| N2kError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::Time> for N2kTime {
+    fn from(time: time::Time) -> Self {
+        let (hour, minute, second, nano) = time.as_hms_nano();
+        N2kTime::from_hms_nano(hour, minute, second, nano).unwrap()
+    }
+}
+
+/// Text or bytes deserialized without an allocator, longer ones are cut off
+#[cfg(feature = "serde")]
+pub struct SerdeBuf {
+    buf: [u8; 255],
+    len: usize,
+}
+
+#[cfg(feature = "serde")]
+impl SerdeBuf {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Text deserialized from a string, empty for bytes that are no UTF-8
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(self.as_bytes()).unwrap_or("")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SerdeBuf {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = SerdeBuf;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str("a string or bytes")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<SerdeBuf, E> {
+                let mut len = value.len().min(255);
+                while !value.is_char_boundary(len) {
+                    len -= 1;
+                }
+                self.visit_bytes(&value.as_bytes()[..len])
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, value: &[u8]) -> Result<SerdeBuf, E> {
+                let len = value.len().min(255);
+                let mut buf = [0; 255];
+                buf[..len].copy_from_slice(&value[..len]);
+                Ok(SerdeBuf { buf, len })
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<SerdeBuf, A::Error> {
+                let mut buf = [0; 255];
+                let mut len = 0;
+                while let Some(byte) = seq.next_element()? {
+                    if len < buf.len() {
+                        buf[len] = byte;
+                        len += 1;
+                    }
+                }
+                Ok(SerdeBuf { buf, len })
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+/// Values serialize as they are, "not available" as none and the other reserved values as
+/// "OutOfRange" and "Reserved"
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for N2kValue<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            N2kValue::Value(value) => value.serialize(serializer),
+            N2kValue::NotAvailable => serializer.serialize_none(),
+            N2kValue::OutOfRange => serializer.serialize_str("OutOfRange"),
+            N2kValue::Reserved => serializer.serialize_str("Reserved"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for N2kValue<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::IntoDeserializer;
+
+        struct Visitor<T>(core::marker::PhantomData<T>);
+
+        impl<'de, T: serde::Deserialize<'de>> serde::de::Visitor<'de> for Visitor<T> {
+            type Value = N2kValue<T>;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str("a value, none, \"OutOfRange\" or \"Reserved\"")
+            }
+
+            fn visit_none<E: serde::de::Error>(self) -> Result<N2kValue<T>, E> {
+                Ok(N2kValue::NotAvailable)
+            }
+
+            fn visit_unit<E: serde::de::Error>(self) -> Result<N2kValue<T>, E> {
+                Ok(N2kValue::NotAvailable)
+            }
+
+            fn visit_some<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<N2kValue<T>, D::Error> {
+                serde::Deserialize::deserialize(deserializer)
+            }
+
+            fn visit_bool<E: serde::de::Error>(self, value: bool) -> Result<N2kValue<T>, E> {
+                T::deserialize(value.into_deserializer()).map(N2kValue::Value)
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<N2kValue<T>, E> {
+                T::deserialize(value.into_deserializer()).map(N2kValue::Value)
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<N2kValue<T>, E> {
+                T::deserialize(value.into_deserializer()).map(N2kValue::Value)
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<N2kValue<T>, E> {
+                T::deserialize(value.into_deserializer()).map(N2kValue::Value)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<N2kValue<T>, E> {
+                match value {
+                    "OutOfRange" => Ok(N2kValue::OutOfRange),
+                    "Reserved" => Ok(N2kValue::Reserved),
+                    value => T::deserialize(value.into_deserializer()).map(N2kValue::Value),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(Visitor(core::marker::PhantomData))
+    }
+}
+
+/// Serializes the `Display` text of the value
+#[cfg(feature = "serde")]
+macro_rules! serialize_display {
+    ($($name:ident$(<$lifetime:lifetime>)?),*) => {
+        $(
+            impl$(<$lifetime>)? serde::Serialize for $name$(<$lifetime>)? {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "serde")]
+serialize_display!(N2kDate, N2kTime, N2kString<'a>);
+
+/// Deserializes values from the text `FromStr` parses
+#[cfg(feature = "serde")]
+macro_rules! deserialize_from_str {
+    ($($name:ident),*) => {
+        $(
+            impl<'de> serde::Deserialize<'de> for $name {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let text = <SerdeBuf as serde::Deserialize>::deserialize(deserializer)?;
+                    text.as_str().parse().map_err(|_| {
+                        serde::de::Error::invalid_value(
+                            serde::de::Unexpected::Str(text.as_str()),
+                            &stringify!($name),
+                        )
+                    })
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "serde")]
+deserialize_from_str!(N2kDate, N2kTime);
+
+#[cfg(feature = "serde")]
+impl<'m, G: RepeatingGroup<'m> + serde::Serialize> serde::Serialize for Repeated<'m, G> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.clone())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for RawPgn {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut raw = serializer.serialize_struct("RawPgn", 2)?;
+        raw.serialize_field("pgn", &self.pgn)?;
+        raw.serialize_field("data", self.data())?;
+        raw.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RawPgn {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        struct Raw {
+            pgn: u32,
+            data: SerdeBuf,
+        }
+        let raw = <Raw as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(RawPgn::new(raw.pgn, raw.data.as_bytes()))
+    }
+}
+
+/// Name of a lookup table value or flag, or the number of one without a name
+#[cfg(feature = "serde")]
+pub enum NameOrNumber {
+    Name(SerdeBuf),
+    Number(u64),
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NameOrNumber {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = NameOrNumber;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str("a name or a number")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<NameOrNumber, E> {
+                Ok(NameOrNumber::Number(value))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<NameOrNumber, E> {
+                serde::Deserialize::deserialize(value.into_deserializer()).map(NameOrNumber::Name)
+            }
+        }
+
+        use serde::de::IntoDeserializer;
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+/// Serializes a lookup table by the names of its variants, values without one as numbers
+#[cfg(feature = "serde")]
+macro_rules! serde_lookup {
+    ($name:ident, $raw:ty) => {
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self.name() {
+                    Some(name) => serializer.serialize_str(name),
+                    None => serializer.serialize_u64(<$raw>::from(*self) as u64),
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                use serde::de::{Error, Unexpected};
+                match <NameOrNumber as serde::Deserialize>::deserialize(deserializer)? {
+                    NameOrNumber::Name(name) => $name::from_name(name.as_str()).ok_or_else(|| {
+                        D::Error::invalid_value(Unexpected::Str(name.as_str()), &stringify!($name))
+                    }),
+                    NameOrNumber::Number(value) => {
+                        <$raw as core::convert::TryFrom<u64>>::try_from(value)
+                            .map($name::from)
+                            .map_err(|_| {
+                                D::Error::invalid_value(Unexpected::Unsigned(value), &stringify!($raw))
+                            })
+                    }
+                }
+            }
+        }
+    };
+}
+#[cfg(feature = "serde")]
+pub(crate) use serde_lookup;
+
+/// Serializes flags as a list of the names of those that are set, bits without a name as their
+/// number
+#[cfg(feature = "serde")]
+macro_rules! serde_flags {
+    ($name:ident, $raw:ty) => {
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeSeq;
+                let named = Self::FLAGS.iter().fold(0, |named, (_, flag)| named | flag.bits());
+                let unnamed = self.bits() & !named;
+                let mut seq = serializer.serialize_seq(None)?;
+                for (name, _) in self.iter() {
+                    seq.serialize_element(name)?;
+                }
+                for bit in (0..<$raw>::BITS).filter(|bit| unnamed >> bit & 1 == 1) {
+                    seq.serialize_element(&bit)?;
+                }
+                seq.end()
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                        f.write_str("a list of flag names or bit numbers")
+                    }
+
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                        self,
+                        mut seq: A,
+                    ) -> Result<$name, A::Error> {
+                        use serde::de::{Error, Unexpected};
+                        let mut flags = $name::empty();
+                        while let Some(flag) = seq.next_element()? {
+                            flags.insert(match flag {
+                                NameOrNumber::Name(name) => $name::FLAGS
+                                    .iter()
+                                    .find(|(flag, _)| *flag == name.as_str())
+                                    .map(|(_, flag)| *flag)
+                                    .ok_or_else(|| {
+                                        A::Error::invalid_value(
+                                            Unexpected::Str(name.as_str()),
+                                            &stringify!($name),
+                                        )
+                                    })?,
+                                NameOrNumber::Number(bit) if bit < <$raw>::BITS as u64 => {
+                                    $name::from_bits(1 << bit)
+                                }
+                                NameOrNumber::Number(bit) => {
+                                    return Err(A::Error::invalid_value(
+                                        Unexpected::Unsigned(bit),
+                                        &"a bit number",
+                                    ))
+                                }
+                            });
+                        }
+                        Ok(flags)
+                    }
+                }
+
+                deserializer.deserialize_seq(Visitor)
+            }
+        }
+    };
 }
+#[cfg(feature = "serde")]
+pub(crate) use serde_flags;
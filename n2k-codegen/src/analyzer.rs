@@ -0,0 +1,180 @@
+//! Lines of canboat's `analyzer -json`, which Signal K and many other tools read. Only PGNs
+//! decoded by the `Decoder` are formatted, generated crates are `no_std` and have no JSON output
+//! of this shape.
+
+use crate::canboatxml::Field;
+use crate::decoder::{Decoded, FieldValue, Value};
+use std::fmt::Write;
+
+/// JSON object of `decoded` as `analyzer -json` prints it on one line, with the `timestamp`
+/// as given and priority, source and destination of `id`. Fields are keyed by their canboat
+/// names and left out if they are not available, repeating groups are in a `list`.
+pub fn to_json(timestamp: &str, id: &n2k::Id, decoded: &Decoded<'_>) -> String {
+    let mut json = String::new();
+    write!(
+        json,
+        "{{\"timestamp\":{},\"prio\":{},\"src\":{},\"dst\":{},\"pgn\":{},\"description\":{},\"fields\":{{",
+        string(timestamp),
+        id.priority() as u8,
+        id.source(),
+        id.destination(),
+        decoded.info.pgn,
+        string(&decoded.info.description),
+    )
+    .unwrap();
+    let mut separator = write_fields(&mut json, &decoded.fields);
+    if !decoded.groups.is_empty() {
+        write!(json, "{}\"list\":[", separator).unwrap();
+        separator = "";
+        for group in &decoded.groups {
+            json.push_str(separator);
+            json.push('{');
+            write_fields(&mut json, group);
+            json.push('}');
+            separator = ",";
+        }
+        json.push(']');
+    }
+    json.push_str("}}");
+    json
+}
+
+/// Writes the `"name":value` pairs of `values`, returns the separator of the next one
+fn write_fields(json: &mut String, values: &[FieldValue<'_>]) -> &'static str {
+    let mut separator = "";
+    for value in values {
+        if let Some(formatted) = format_value(value.field, &value.value) {
+            write!(
+                json,
+                "{}{}:{}",
+                separator,
+                string(&value.field.name),
+                formatted
+            )
+            .unwrap();
+            separator = ",";
+        }
+    }
+    separator
+}
+
+/// JSON of a value in the units of analyzer: degrees instead of radians and Celsius instead
+/// of Kelvin, with as many decimals as the resolution has. None for reserved values.
+fn format_value(field: &Field, value: &Value<'_>) -> Option<String> {
+    Some(match value {
+        Value::Integer(value) => value.to_string(),
+        Value::Number(value) => {
+            let (value, resolution) = match field.unit() {
+                Some("rad") | Some("rad/s") => (value.to_degrees(), field.scale().to_degrees()),
+                Some("K") => (value - 273.15, field.scale()),
+                _ => (*value, field.scale()),
+            };
+            format!("{:.*}", decimals(resolution), value)
+        }
+        Value::Text(text) => string(text),
+        Value::Lookup(_, Some(name)) => string(name),
+        Value::Lookup(value, None) => value.to_string(),
+        Value::Flags(_, names) => string(&names.join(", ")),
+        Value::Date(days) => {
+            let (year, month, day) = civil_from_days(*days as i64);
+            string(&format!("{:04}.{:02}.{:02}", year, month, day))
+        }
+        Value::Time(time) => string(&format!(
+            "{:02}:{:02}:{:02}.{:04}",
+            time / 36_000_000,
+            time / 600_000 % 60,
+            time / 10_000 % 60,
+            time % 10_000
+        )),
+        Value::Binary(bytes) => {
+            let hex: Vec<_> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            string(&hex.join(" "))
+        }
+        Value::NotAvailable | Value::OutOfRange | Value::Reserved => return None,
+    })
+}
+
+/// Decimals needed to show steps of `resolution`, f64 holds no more than 10 of degrees
+fn decimals(resolution: f64) -> usize {
+    let mut decimals = 0;
+    let mut step = resolution;
+    while step < 1.0 - 1e-9 && decimals < 10 {
+        step *= 10.0;
+        decimals += 1;
+    }
+    decimals
+}
+
+fn string(text: &str) -> String {
+    serde_json::to_string(text).unwrap()
+}
+
+/// Year, month and day of `days` since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::Decoder;
+    use n2k::{Id, Priority};
+
+    fn decoder() -> Decoder {
+        Decoder::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/pgns.xml")).unwrap()
+    }
+
+    #[test]
+    fn analyzer_json() {
+        let decoder = decoder();
+        let id = Id::new(Priority::Priority2, 127250, 3, 255).unwrap();
+        let decoded = decoder
+            .decode(127250, &[0x01, 0x10, 0x27, 0xff, 0x7f, 0x64, 0x00, 0xfd])
+            .unwrap();
+        assert_eq!(
+            to_json("2021-03-07T10:15:23.123Z", &id, &decoded),
+            "{\"timestamp\":\"2021-03-07T10:15:23.123Z\",\"prio\":2,\"src\":3,\"dst\":255,\"pgn\":127250,\"description\":\"Vessel Heading\",\"fields\":{\"SID\":1,\"Heading\":57.296,\"Variation\":0.573,\"Reference\":\"Magnetic\"}}"
+        );
+
+        let id = Id::new(Priority::Priority3, 126992, 5, 255).unwrap();
+        let decoded = decoder
+            .decode(126992, &[0x01, 0xf0, 0xc4, 0x48, 0x00, 0x51, 0x25, 0x02])
+            .unwrap();
+        assert_eq!(
+            to_json("t", &id, &decoded),
+            "{\"timestamp\":\"t\",\"prio\":3,\"src\":5,\"dst\":255,\"pgn\":126992,\"description\":\"System Time\",\"fields\":{\"SID\":1,\"Source\":\"GPS\",\"Date\":\"2021.01.01\",\"Time\":\"01:00:00.0000\"}}"
+        );
+    }
+
+    #[test]
+    fn analyzer_json_list() {
+        let decoder = decoder();
+        let id = Id::new(Priority::Priority6, 129540, 1, 255).unwrap();
+        let payload = [
+            0x01, 0xfd, 0x01, //
+            0x05, 0x10, 0x27, 0x20, 0x4e, 0xe8, 0x03, 0xff, 0xff, 0xff, 0x7f, 0xf2,
+        ];
+        let decoded = decoder.decode(129540, &payload).unwrap();
+        assert_eq!(
+            to_json("t", &id, &decoded),
+            "{\"timestamp\":\"t\",\"prio\":6,\"src\":1,\"dst\":255,\"pgn\":129540,\"description\":\"GNSS Sats in View\",\"fields\":{\"SID\":1,\"Mode\":1,\"Sats in View\":1,\"list\":[{\"PRN\":5,\"Elevation\":57.296,\"Azimuth\":114.592,\"SNR\":10.00,\"Status\":\"Used\"}]}}"
+        );
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(18628), (2021, 1, 1));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    }
+}
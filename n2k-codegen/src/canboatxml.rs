@@ -1,3 +1,4 @@
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 struct PgnDefinition {
     #[serde(rename = "PGN")]
@@ -16,6 +17,7 @@ struct PgnDefinition {
     pub fields: Vec<PgnDefinitionField>,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 struct PgnDefinitionField {
     #[serde(rename = "Id")]
@@ -46,26 +48,28 @@ pub struct PgnInfo {
     pub pgn: u32,
     #[serde(rename = "Id")]
     pub id: String,
+    #[serde(rename = "Description", default)]
+    pub description: String,
+    /// Default priority, only in newer versions of the file
+    #[serde(rename = "Priority", default)]
+    pub priority: Option<u8>,
     #[serde(rename = "Length")]
     pub length: usize,
     #[serde(rename = "Type")]
     pub xtype: String,
+    /// Number of fields at the end that repeat
+    #[serde(rename = "RepeatingFields", default)]
+    pub repeating_fields: usize,
     #[serde(rename = "Fields", default)]
     pub fields: Fields,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Fields {
     #[serde(rename = "Field", default)]
     pub fields: Vec<Field>,
 }
 
-impl std::default::Default for Fields {
-    fn default() -> Fields {
-        Fields { fields: vec![] }
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Field {
     #[serde(rename = "Order")]
@@ -84,24 +88,27 @@ pub struct Field {
     pub n2k_type: String,
     #[serde(rename = "Resolution", default)]
     pub resolution: f32,
+    /// Added to the raw value before scaling it
+    #[serde(rename = "Offset", default)]
+    pub offset: i64,
+    #[serde(rename = "Units", default)]
+    pub units: String,
     #[serde(rename = "EnumValues", default)]
     pub enum_values: EnumValues,
+    /// Names of the bits of a bitfield
+    #[serde(rename = "EnumBitValues", default)]
+    pub enum_bit_values: EnumBitValues,
+    /// Value the field has in this variant of the PGN
+    #[serde(rename = "Match", default)]
+    pub match_value: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct EnumValues {
     #[serde(rename = "EnumPair", default)]
     pub enum_values: Vec<EnumPair>,
 }
 
-impl std::default::Default for EnumValues {
-    fn default() -> EnumValues {
-        EnumValues {
-            enum_values: vec![],
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EnumPair {
     #[serde(rename = "Value")]
@@ -109,3 +116,17 @@ pub struct EnumPair {
     #[serde(rename = "Name")]
     pub name: String,
 }
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct EnumBitValues {
+    #[serde(rename = "EnumPair", default)]
+    pub enum_bit_values: Vec<EnumBitPair>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EnumBitPair {
+    #[serde(rename = "Bit")]
+    pub bit: usize,
+    #[serde(rename = "Name")]
+    pub name: String,
+}
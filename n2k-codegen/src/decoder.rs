@@ -0,0 +1,443 @@
+//! Decoding of any PGN of pgns.xml at runtime, for tools that come across PGNs no crate was
+//! generated for. Fields are laid out and scaled like in the generated code.
+
+use crate::canboatxml::{Field, PgnInfo, PgnsFile};
+use crate::{decoded_enum_values, dedup_ids, StringEncoding};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(std::io::Error),
+    Xml(serde_xml_rs::Error),
+    /// No PGN in the file with this number, or none of its variants matched the payload
+    UnknownPgn(u32),
+}
+
+/// Value of a field
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'d> {
+    Integer(i64),
+    /// Value scaled by the resolution of the field
+    Number(f64),
+    Text(String),
+    /// Value of a lookup table and its name, None if the table has none for it
+    Lookup(u32, Option<&'d str>),
+    /// Bitfield and the names of the bits that are set
+    Flags(u64, Vec<&'d str>),
+    /// Days since 1970-01-01
+    Date(u16),
+    /// 0.0001 s since midnight
+    Time(u32),
+    Binary(Vec<u8>),
+    NotAvailable,
+    OutOfRange,
+    Reserved,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldValue<'d> {
+    pub field: &'d Field,
+    pub value: Value<'d>,
+}
+
+/// PGN decoded at runtime. Reserved fields and fields that were not received are left out.
+#[derive(Debug, Clone)]
+pub struct Decoded<'d> {
+    pub info: &'d PgnInfo,
+    pub fields: Vec<FieldValue<'d>>,
+    /// Fields of each repeating group received
+    pub groups: Vec<Vec<FieldValue<'d>>>,
+}
+
+impl<'d> Decoded<'d> {
+    /// Value of the field with the canboat id `id`, outside of the repeating groups
+    pub fn get(&self, id: &str) -> Option<&Value<'d>> {
+        self.fields
+            .iter()
+            .find(|value| value.field.id == id)
+            .map(|value| &value.value)
+    }
+}
+
+/// PGNs of a pgns.xml by number
+pub struct Decoder {
+    pgns: PgnsFile,
+    /// Indices of the variants of each PGN in the order they are matched
+    variants: HashMap<u32, Vec<usize>>,
+}
+
+impl Decoder {
+    /// Ids are made unique like in the generated code
+    pub fn new(mut pgns: PgnsFile) -> Self {
+        dedup_ids(&mut pgns);
+        let mut variants: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (index, info) in pgns.pgns.pgn_infos.iter().enumerate() {
+            variants.entry(info.pgn).or_default().push(index);
+        }
+        Self { pgns, variants }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DecodeError> {
+        let xml = std::fs::read_to_string(path).map_err(DecodeError::Io)?;
+        let pgns = serde_xml_rs::from_str(&xml).map_err(DecodeError::Xml)?;
+        Ok(Self::new(pgns))
+    }
+
+    pub fn pgns(&self) -> &PgnsFile {
+        &self.pgns
+    }
+
+    /// Variant of `pgn` whose match fields have their values in `payload`
+    pub fn lookup(&self, pgn: u32, payload: &[u8]) -> Option<&PgnInfo> {
+        self.variants
+            .get(&pgn)?
+            .iter()
+            .map(|index| &self.pgns.pgns.pgn_infos[*index])
+            .find(|info| {
+                info.fields
+                    .fields
+                    .iter()
+                    .all(|field| match field.match_value {
+                        Some(value) => {
+                            field.bit_length <= 64
+                                && field.bit_offset + field.bit_length <= payload.len() * 8
+                                && load_bits(payload, field.bit_offset, field.bit_length) == value
+                        }
+                        None => true,
+                    })
+            })
+    }
+
+    pub fn decode(&self, pgn: u32, payload: &[u8]) -> Result<Decoded<'_>, DecodeError> {
+        let info = self
+            .lookup(pgn, payload)
+            .ok_or(DecodeError::UnknownPgn(pgn))?;
+        let fields = &info.fields.fields;
+        let (fixed, group) =
+            fields.split_at(fields.len() - info.repeating_fields.min(fields.len()));
+        let mut reader = Reader {
+            payload,
+            variable_length: info.is_variable_length(),
+            cursor: 0,
+            moved: false,
+        };
+
+        let mut decoded = Decoded {
+            info,
+            fields: vec![],
+            groups: vec![],
+        };
+        let mut count = None;
+        for field in fixed {
+            let value = match reader.read(field) {
+                Some(value) => value,
+                None => return Ok(decoded),
+            };
+            if !group.is_empty() && field.is_count() {
+                if let Value::Integer(value) = value {
+                    count = Some(value as usize);
+                }
+            }
+            if field.id != "reserved" {
+                decoded.fields.push(FieldValue { field, value });
+            }
+        }
+
+        // Without a count the groups go on until the end of the payload
+        if let Some(first) = group.first() {
+            if !reader.moved {
+                reader.cursor = first.bit_offset;
+            }
+            reader.moved = true;
+        }
+        while !group.is_empty() && count.is_none_or(|count| decoded.groups.len() < count) {
+            let start = reader.cursor;
+            let values: Option<Vec<_>> = group
+                .iter()
+                .map(|field| reader.read(field).map(|value| FieldValue { field, value }))
+                .collect();
+            match values {
+                Some(values) if reader.cursor > start => decoded.groups.push(
+                    values
+                        .into_iter()
+                        .filter(|value| value.field.id != "reserved")
+                        .collect(),
+                ),
+                _ => break,
+            }
+        }
+        Ok(decoded)
+    }
+}
+
+/// Walks the fields of a payload. canboat gives no offset for fields after a variable length
+/// string, from there on they follow each other.
+struct Reader<'p> {
+    payload: &'p [u8],
+    variable_length: bool,
+    /// Bit after the last field read
+    cursor: usize,
+    /// Whether fields are placed at the cursor instead of their offset
+    moved: bool,
+}
+
+impl<'p> Reader<'p> {
+    /// Value of the next field, None if it was not received
+    fn read<'d>(&mut self, field: &'d Field) -> Option<Value<'d>> {
+        let start = if self.moved {
+            self.cursor
+        } else {
+            field.bit_offset
+        };
+        let (value, bit_length) = match field.string_encoding() {
+            Some(encoding) => {
+                let data = self.payload.get(start / 8..)?;
+                let data = if self.variable_length {
+                    data
+                } else {
+                    data.get(..field.bit_length / 8)?
+                };
+                let len = string_len(encoding, data)?;
+                let bit_length = if self.variable_length {
+                    self.moved = true;
+                    len * 8
+                } else {
+                    field.bit_length
+                };
+                (
+                    Value::Text(decode_string(encoding, &data[..len])),
+                    bit_length,
+                )
+            }
+            None if start + field.bit_length > self.payload.len() * 8 => return None,
+            None => (decode_value(field, self.payload, start), field.bit_length),
+        };
+        self.cursor = start + bit_length;
+        Some(value)
+    }
+}
+
+fn decode_value<'d>(field: &'d Field, payload: &[u8], start: usize) -> Value<'d> {
+    let bit_length = field.bit_length;
+    if field.is_string() {
+        return Value::Text(ascii_text(&load_bytes(payload, start, bit_length)));
+    }
+    if field.is_enum() && bit_length <= 32 {
+        let value = load_bits(payload, start, bit_length) as u32;
+        let name = field
+            .enum_values
+            .enum_values
+            .iter()
+            .zip(decoded_enum_values(&field.enum_values))
+            .find(|(_, decoded)| decoded.parse() == Ok(value))
+            .map(|(pair, _)| pair.name.as_str());
+        return Value::Lookup(value, name);
+    }
+    if field.is_bitfield() {
+        let value = load_bits(payload, start, bit_length);
+        let names = field
+            .enum_bit_values
+            .enum_bit_values
+            .iter()
+            .filter(|pair| pair.bit < 64 && value & (1 << pair.bit) != 0)
+            .map(|pair| pair.name.as_str())
+            .collect();
+        return Value::Flags(value, names);
+    }
+    if bit_length == 0
+        || bit_length > 64
+        || field.n2k_type == "Binary data"
+        || field.n2k_type == "Bitfield"
+    {
+        return Value::Binary(load_bytes(payload, start, bit_length));
+    }
+
+    let raw = load_bits(payload, start, bit_length);
+    let raw = if field.is_signed() {
+        // Sign extend
+        ((raw << (64 - bit_length)) as i64 >> (64 - bit_length)) as i128
+    } else {
+        raw as i128
+    };
+    if field.is_date_time()
+        || field.is_float()
+        || (field.offset != 0 && bit_length <= 32)
+        || field.is_plain_integer()
+    {
+        let [not_available, out_of_range, reserved] = field.reserved_values();
+        match raw {
+            raw if raw == not_available => return Value::NotAvailable,
+            raw if raw == out_of_range => return Value::OutOfRange,
+            raw if raw == reserved => return Value::Reserved,
+            _ => {}
+        }
+    }
+    match field.n2k_type.as_str() {
+        "Date" => Value::Date(raw as u16),
+        "Time" => Value::Time(raw as u32),
+        _ if field.is_float() => Value::Number((raw + field.offset as i128) as f64 * field.scale()),
+        _ => Value::Integer((raw + field.offset as i128) as i64),
+    }
+}
+
+/// `bit_length` bits at `start`, least significant bit first
+fn load_bits(payload: &[u8], start: usize, bit_length: usize) -> u64 {
+    (0..bit_length).fold(0, |value, bit| {
+        let index = start + bit;
+        value | (((payload[index / 8] >> (index % 8)) & 1) as u64) << bit
+    })
+}
+
+/// `bit_length` bits at `start` as bytes, the last one is padded with zeros
+fn load_bytes(payload: &[u8], start: usize, bit_length: usize) -> Vec<u8> {
+    (0..bit_length)
+        .step_by(8)
+        .map(|bit| load_bits(payload, start + bit, (bit_length - bit).min(8)) as u8)
+        .collect()
+}
+
+/// Text of a fixed length string without its padding
+fn ascii_text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .rposition(|b| !matches!(b, 0x00 | 0xff | b'@' | b' '))
+        .map_or(0, |i| i + 1);
+    match std::str::from_utf8(&bytes[..end]) {
+        Ok(text) => text.to_owned(),
+        Err(_) => bytes[..end].iter().map(|b| *b as char).collect(),
+    }
+}
+
+/// Bytes taken by the string at the start of `data`, None if it was not received completely
+fn string_len(encoding: StringEncoding, data: &[u8]) -> Option<usize> {
+    let len = match encoding {
+        StringEncoding::Lau => (*data.first()? as usize).max(2),
+        StringEncoding::Lz => *data.first()? as usize + 1,
+        StringEncoding::StartStop => match *data.first()? {
+            0x02 => data.iter().position(|b| *b == 0x01)? + 1,
+            // some senders use a total length byte instead
+            len if len > 0x02 => len as usize,
+            _ => 1,
+        },
+    };
+    Some(len).filter(|len| *len <= data.len())
+}
+
+/// Text of the string in `field`, which is exactly `string_len` bytes long
+fn decode_string(encoding: StringEncoding, field: &[u8]) -> String {
+    match encoding {
+        StringEncoding::Lau if field[1] == 0 => {
+            let units = field[2..]
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .take_while(|unit| *unit != 0);
+            std::char::decode_utf16(units)
+                .map(|c| c.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        StringEncoding::Lau => ascii_text(&field[2..]),
+        StringEncoding::Lz => ascii_text(&field[1..]),
+        StringEncoding::StartStop => match field[0] {
+            0x02 => ascii_text(&field[1..field.len() - 1]),
+            len if len > 0x02 && field.get(1) == Some(&0x01) => ascii_text(&field[2..]),
+            len if len > 0x02 => ascii_text(&field[1..]),
+            _ => String::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoder() -> Decoder {
+        Decoder::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/pgns.xml")).unwrap()
+    }
+
+    #[test]
+    fn numbers_and_lookups() {
+        let decoder = decoder();
+        // 1 rad heading, deviation not available, variation out of range, magnetic reference
+        let payload = [0x01, 0x10, 0x27, 0xff, 0x7f, 0xfe, 0x7f, 0xfd];
+        let decoded = decoder.decode(127250, &payload).unwrap();
+        assert_eq!(decoded.info.id, "vesselHeading");
+        assert_eq!(decoded.get("sid"), Some(&Value::Integer(1)));
+        assert_eq!(decoded.get("heading"), Some(&Value::Number(1.0)));
+        assert_eq!(decoded.get("deviation"), Some(&Value::NotAvailable));
+        assert_eq!(decoded.get("variation"), Some(&Value::OutOfRange));
+        assert_eq!(
+            decoded.get("reference"),
+            Some(&Value::Lookup(1, Some("Magnetic")))
+        );
+        assert_eq!(decoded.get("reserved"), None);
+
+        // Fields that were not received are left out
+        let decoded = decoder.decode(127250, &payload[..4]).unwrap();
+        assert_eq!(decoded.fields.len(), 2);
+
+        assert!(matches!(
+            decoder.decode(1, &payload),
+            Err(DecodeError::UnknownPgn(1))
+        ));
+    }
+
+    #[test]
+    fn repeating_groups() {
+        let decoder = decoder();
+        // 2 satellites in view, the second one without a status
+        let payload = [
+            0x01, 0xfd, 0x02, //
+            0x05, 0x10, 0x27, 0x20, 0x4e, 0xe8, 0x03, 0xff, 0xff, 0xff, 0x7f, 0xf2, //
+            0x07, 0x10, 0x27, 0x20, 0x4e, 0xe8, 0x03, 0xff, 0xff, 0xff, 0x7f,
+        ];
+        let decoded = decoder.decode(129540, &payload).unwrap();
+        assert_eq!(decoded.get("satsInView"), Some(&Value::Integer(2)));
+        assert_eq!(decoded.groups.len(), 1);
+        let values: Vec<_> = decoded.groups[0].iter().map(|v| &v.value).collect();
+        assert_eq!(
+            values,
+            [
+                &Value::Integer(5),
+                &Value::Number(1.0),
+                &Value::Number(2.0),
+                &Value::Number(10.0),
+                &Value::NotAvailable,
+                &Value::Lookup(2, Some("Used")),
+            ]
+        );
+    }
+
+    #[test]
+    fn variable_length_strings() {
+        let decoder = decoder();
+        let payload = [
+            0x04, 0x01, b'a', b'b', //
+            0x06, 0x00, b'c', 0x00, b'd', 0x00, //
+            0x02, 0x01,
+        ];
+        let decoded = decoder.decode(126998, &payload).unwrap();
+        assert_eq!(
+            decoded.get("installationDescription1"),
+            Some(&Value::Text("ab".to_owned()))
+        );
+        assert_eq!(
+            decoded.get("installationDescription2"),
+            Some(&Value::Text("cd".to_owned()))
+        );
+        assert_eq!(
+            decoded.get("manufacturerInformation"),
+            Some(&Value::Text(String::new()))
+        );
+    }
+
+    #[test]
+    fn variants() {
+        let decoder = decoder();
+        let lookup = |payload: &[u8]| decoder.lookup(130820, payload).map(|info| &info.id[..]);
+        assert_eq!(lookup(&[0x41, 0x9f, 0x01]), Some("simnetReprogramStatus1"));
+        assert_eq!(lookup(&[0xa3, 0x99, 0x02]), Some("fusionSourceName"));
+        assert_eq!(lookup(&[0xa3, 0x99, 0x04]), Some("fusionTrackInfo"));
+        assert_eq!(lookup(&[0xa3, 0x99]), None);
+    }
+}
@@ -3,20 +3,26 @@ extern crate serde_derive;
 
 use heck::*;
 use log::*;
-use proc_macro2::{Ident, Span, TokenStream};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
 use quote::{format_ident, quote};
 use std::{
     collections::{HashMap, HashSet},
+    ops::Range,
     path::Path,
 };
 use std::{fs::File, str::FromStr};
 use std::{io::Write, path::PathBuf};
 
-mod canboatxml;
+pub mod analyzer;
+pub mod canboatxml;
+pub mod decoder;
 mod keywords;
 
 use canboatxml::*;
 
+/// Largest payload of a fast packet
+const MAX_FAST_PACKET_SIZE: usize = 223;
+
 pub struct N2kCodeGenOpts {
     pub pgns_xml: String,
     pub pgns: HashSet<u32>,
@@ -30,7 +36,8 @@ pub fn codegen(opts: N2kCodeGenOpts) {
     std::fs::create_dir_all(&dest_path).ok();
 
     let my_str = std::fs::read_to_string(&opts.pgns_xml).unwrap();
-    let content: PgnsFile = serde_xml_rs::from_str(&my_str).unwrap();
+    let mut content: PgnsFile = serde_xml_rs::from_str(&my_str).unwrap();
+    dedup_ids(&mut content);
 
     // create Cargo.toml
     if let Some(crate_name) = opts.generate_crate.as_ref() {
@@ -58,8 +65,13 @@ pub fn codegen(opts: N2kCodeGenOpts) {
     };
 
     let mut lib_file = File::create(&lib_path).unwrap();
-    writeln!(lib_file, "mod messages;").unwrap();
+    writeln!(lib_file, "pub mod messages;").unwrap();
     writeln!(lib_file, "mod types;").unwrap();
+    writeln!(
+        lib_file,
+        "pub use types::{{FieldDescriptor, GeoPosition, N2kDate, N2kString, N2kTime, N2kValue, PgnDescriptor, RawPgn, Repeated, RepeatingGroup, StringEncoding}};"
+    )
+    .unwrap();
 
     // PGNs enum with all PGNs
     writeln!(lib_file, "mod pgns;").unwrap();
@@ -82,6 +94,17 @@ pub fn codegen(opts: N2kCodeGenOpts) {
     let pgns_file_path = dest_path.join("registry.rs");
     std::fs::write(pgns_file_path, pgns_file.to_string()).unwrap();
 
+    // Layouts of the PGNs
+    writeln!(lib_file, "mod descriptors;").unwrap();
+    writeln!(
+        lib_file,
+        "pub use descriptors::{{pgn_descriptor, pgn_descriptors, PGN_DESCRIPTORS}};"
+    )
+    .unwrap();
+    let pgns_file = codegen_pgns_descriptors(&content, &opts.pgns);
+    let pgns_file_path = dest_path.join("descriptors.rs");
+    std::fs::write(pgns_file_path, pgns_file.to_string()).unwrap();
+
     std::fs::create_dir_all(dest_path.join("messages")).ok();
     let gen_lib_path = dest_path.join("messages/mod.rs");
     let mut gen_lib_file = File::create(&gen_lib_path).unwrap();
@@ -108,6 +131,35 @@ pub fn codegen(opts: N2kCodeGenOpts) {
     }
 }
 
+/// Variants of proprietary PGNs reuse ids, as do a few fields of the same PGN. Handle duplicates
+/// by adding a counter to the end.
+fn dedup_ids(pgns_file: &mut PgnsFile) {
+    let mut seen_ids: HashMap<String, u32> = HashMap::new();
+    for info in &mut pgns_file.pgns.pgn_infos {
+        dedup_id(&mut seen_ids, &mut info.id);
+
+        // Reserved fields have no accessors or types
+        let mut seen_field_ids = HashMap::new();
+        for field in &mut info.fields.fields {
+            if field.id != "reserved" {
+                dedup_id(&mut seen_field_ids, &mut field.id);
+            }
+        }
+    }
+}
+
+fn dedup_id(seen_ids: &mut HashMap<String, u32>, id: &mut String) {
+    match seen_ids.get_mut(id) {
+        Some(counter) => {
+            *id = format!("{}{}", id, counter);
+            *counter += 1;
+        }
+        None => {
+            seen_ids.insert(id.to_owned(), 1);
+        }
+    }
+}
+
 /// Generate an implementation of the PgnRegistry trait to be used by the n2k embedded_hal_can library
 fn codegen_pgns_registry_impl(pgns_file: &PgnsFile, pgns: &HashSet<u32>) -> TokenStream {
     let mut is_fast_packet = vec![];
@@ -120,20 +172,8 @@ fn codegen_pgns_registry_impl(pgns_file: &PgnsFile, pgns: &HashSet<u32>) -> Toke
             .filter(|pgn| pgn.pgn == *pgn_id)
             .collect();
 
-        if pgns.is_empty() {
-            continue;
-        }
-
-        if pgns.len() > 1 {
-            panic!(
-                "PGNs with more than one variation not supported yet ({})",
-                pgn_id
-            )
-        }
-
-        let pgn = pgns[0];
-
-        if pgn.xtype == "Fast" {
+        // Variants of a PGN share its transport
+        if pgns.iter().any(|pgn| pgn.xtype == "Fast") {
             let pgn_id = TokenStream::from_str(&pgn_id.to_string()).unwrap();
             is_fast_packet.push(quote! {
                 #pgn_id
@@ -166,37 +206,86 @@ fn codegen_pgns_registry_impl(pgns_file: &PgnsFile, pgns: &HashSet<u32>) -> Toke
     }
 }
 
+fn codegen_pgns_descriptors(pgns_file: &PgnsFile, pgns: &HashSet<u32>) -> TokenStream {
+    // In the order of the file, by PGN and variants of a PGN in the order they are matched
+    let descriptors = pgns_file
+        .pgns
+        .pgn_infos
+        .iter()
+        .filter(|info| pgns.contains(&info.pgn))
+        .map(|info| {
+            let struct_name = format_ident!("{}", type_name(&info.id));
+            quote! { crate::#struct_name::DESCRIPTOR }
+        });
+
+    quote! {
+        use crate::types::PgnDescriptor;
+
+        /// Layouts of every generated PGN, ordered by PGN
+        pub static PGN_DESCRIPTORS: &[PgnDescriptor] = &[#(#descriptors),*];
+
+        /// Layout of `pgn`, the first variant of PGNs with several
+        pub fn pgn_descriptor(pgn: u32) -> Option<&'static PgnDescriptor> {
+            pgn_descriptors(pgn).next()
+        }
+
+        /// Layouts of the variants of `pgn`, told apart by the `match_value` of their fields
+        pub fn pgn_descriptors(pgn: u32) -> impl Iterator<Item = &'static PgnDescriptor> {
+            PGN_DESCRIPTORS
+                .iter()
+                .filter(move |descriptor| descriptor.pgn == pgn)
+        }
+    }
+}
+
 fn codegen_pgns_variant_enum(pgns_file: &PgnsFile, pgns: &HashSet<u32>) -> TokenStream {
     let mut variants = vec![];
+    let mut variant_names = vec![];
     let mut match_arms = vec![];
     for pgn_id in pgns {
         // A PGN can map to multiple variants
-        let names: Vec<_> = pgns_file
+        let mut infos: Vec<_> = pgns_file
             .pgns
             .pgn_infos
             .iter()
             .filter(|pgn| pgn.pgn == *pgn_id)
-            .map(|v| type_name(&v.id))
             .collect();
 
-        if names.is_empty() {
+        if infos.is_empty() {
             continue;
         }
 
-        if names.len() > 1 {
-            panic!(
-                "PGNs with more than one variation not supported yet ({})",
-                pgn_id
-            )
+        for info in &infos {
+            let variant_name = Ident::new(&type_name(&info.id), Span::call_site());
+            variants.push(quote! {
+                #variant_name(crate::#variant_name)
+            });
+            variant_names.push(variant_name);
         }
 
-        let variant_name = Ident::new(&names[0], Span::call_site());
-        variants.push(quote! {
-            #variant_name(crate::#variant_name)
-        });
+        if infos.len() == 1 && !infos[0].has_match_fields() {
+            let variant_name = Ident::new(&type_name(&infos[0].id), Span::call_site());
+            match_arms.push(quote! {
+                #pgn_id => Pgn::#variant_name(crate::#variant_name::try_from(bytes)?)
+            });
+            continue;
+        }
 
+        // Variants are picked by their match fields, those without any match everything and
+        // are tried last
+        infos.sort_by_key(|info| !info.has_match_fields());
+        let checks = infos.iter().map(|info| {
+            let variant_name = Ident::new(&type_name(&info.id), Span::call_site());
+            quote! {
+                if crate::#variant_name::matches(bytes) {
+                    Pgn::#variant_name(crate::#variant_name::try_from(bytes)?)
+                }
+            }
+        });
         match_arms.push(quote! {
-            #pgn_id => Pgn::#variant_name(crate::#variant_name::try_from(bytes)?)
+            #pgn_id => #(#checks)else* else {
+                Pgn::Raw(RawPgn::new(pgn, bytes))
+            }
         });
     }
     quote! {
@@ -204,8 +293,11 @@ fn codegen_pgns_variant_enum(pgns_file: &PgnsFile, pgns: &HashSet<u32>) -> Token
         use core::convert::TryFrom;
 
         #[derive(Debug)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         pub enum Pgn {
-            #(#variants),*
+            #(#variants,)*
+            /// Payload of a PGN none of the variants matched
+            Raw(RawPgn)
         }
 
         impl Pgn {
@@ -215,6 +307,72 @@ fn codegen_pgns_variant_enum(pgns_file: &PgnsFile, pgns: &HashSet<u32>) -> Token
                     pgn => return Err(N2kError::UnknownPgn(pgn))
                 })
             }
+
+            pub fn pgn(&self) -> u32 {
+                match self {
+                    #(Pgn::#variant_names(_) => crate::#variant_names::PGN,)*
+                    Pgn::Raw(raw) => raw.pgn(),
+                }
+            }
+
+            /// Name of the PGN in canboat, "Unknown" for raw payloads
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #(Pgn::#variant_names(_) => crate::#variant_names::NAME,)*
+                    Pgn::Raw(_) => "Unknown",
+                }
+            }
+
+            /// Default priority of the PGN, 6 for raw payloads
+            pub fn priority(&self) -> n2k::Priority {
+                match self {
+                    #(Pgn::#variant_names(_) => crate::#variant_names::PRIORITY,)*
+                    Pgn::Raw(_) => n2k::Priority::Priority6,
+                }
+            }
+
+            /// Layout of the PGN, None for raw payloads
+            pub fn descriptor(&self) -> Option<&'static PgnDescriptor> {
+                match self {
+                    #(Pgn::#variant_names(_) => Some(&crate::#variant_names::DESCRIPTOR),)*
+                    Pgn::Raw(_) => None,
+                }
+            }
+
+            /// Whether the PGN is sent as a fast packet
+            pub fn is_fast_packet(&self) -> bool {
+                <crate::PgnRegistry as n2k::PgnRegistry>::is_fast_packet(self.pgn())
+            }
+
+            pub fn as_bytes(&self) -> &[u8] {
+                match self {
+                    #(Pgn::#variant_names(message) => message.as_bytes(),)*
+                    Pgn::Raw(raw) => raw.data(),
+                }
+            }
+
+            /// Copies the payload to the start of `buf` and returns its length
+            pub fn to_bytes(&self, buf: &mut [u8]) -> Result<usize, N2kError> {
+                let bytes = self.as_bytes();
+                if buf.len() < bytes.len() {
+                    return Err(N2kError::BufferTooSmall {
+                        expected: bytes.len(),
+                        actual: buf.len(),
+                    });
+                }
+                buf[..bytes.len()].copy_from_slice(bytes);
+                Ok(bytes.len())
+            }
+
+            /// Identifier of the PGN with its default priority
+            pub fn id(&self, source: u8, destination: u8) -> Result<n2k::Id, n2k::IdError> {
+                n2k::Id::new(self.priority(), self.pgn(), source, destination)
+            }
+
+            /// Message with this payload, such as for `n2k::Bus::send`
+            pub fn to_message(&self, id: n2k::Id) -> Result<n2k::Message<'_>, n2k::MessageError> {
+                n2k::Message::new(id, self.as_bytes())
+            }
         }
     }
 }
@@ -292,24 +450,57 @@ fn codegen_pgn(lib_file: &mut File, gen_lib_file: &mut File, path: &Path, pgninf
     let current_file_path = path.join(&name);
     let mut message_file = File::create(&current_file_path).unwrap();
 
+    // PGNs without fields never look at the bits
+    let bitvec = if pgninfo.fields.fields.is_empty() {
+        quote! {}
+    } else {
+        quote! { use bitvec::prelude::*; }
+    };
     let header = quote! {
-        use bitvec::prelude::*;
+        #bitvec
         use crate::types::*;
     };
     writeln!(message_file, "{}", header).unwrap();
 
     let size = pgninfo.length;
-    let struct_ = quote! {
-        pub struct #struct_name {
-            raw: [u8; #size],
+    let struct_ = if pgninfo.is_variable_length() {
+        let capacity = pgninfo.capacity();
+        quote! {
+            #[derive(Clone)]
+            pub struct #struct_name {
+                raw: [u8; #capacity],
+                len: usize,
+            }
+        }
+    } else {
+        quote! {
+            #[derive(Clone)]
+            pub struct #struct_name {
+                raw: [u8; #size],
+            }
         }
     };
-    let definition_str = format!("// {}", serde_json::to_string(&pgninfo).unwrap());
-    writeln!(message_file, "{}", definition_str).unwrap();
     writeln!(message_file, "{}", struct_).unwrap();
 
     let pgn_id = TokenStream::from_str(&pgninfo.pgn.to_string()).unwrap();
-    let try_from = quote! {
+    let try_from = if pgninfo.is_variable_length() {
+        let capacity = pgninfo.capacity();
+        // Any length is accepted, getters of fields that were not received return None
+        quote! {
+            impl core::convert::TryFrom<&[u8]> for #struct_name {
+                type Error = N2kError;
+
+                #[inline(always)]
+                fn try_from(payload: &[u8]) -> Result<Self, Self::Error> {
+                    let len = payload.len().min(#capacity);
+                    let mut raw = [0xff; #capacity];
+                    raw[..len].copy_from_slice(&payload[..len]);
+                    Ok(Self { raw, len })
+                }
+            }
+        }
+    } else {
+        quote! {
         impl core::convert::TryFrom<&[u8]> for #struct_name {
           type Error = N2kError;
 
@@ -323,6 +514,7 @@ fn codegen_pgn(lib_file: &mut File, gen_lib_file: &mut File, path: &Path, pgninf
                 Ok(Self { raw })
             }
         }
+        }
     };
 
     writeln!(message_file, "{}", try_from).unwrap();
@@ -330,50 +522,234 @@ fn codegen_pgn(lib_file: &mut File, gen_lib_file: &mut File, path: &Path, pgninf
     // Codegen Enums
     for field in &pgninfo.fields.fields {
         if !field.enum_values.enum_values.is_empty() {
-            writeln!(
-                message_file,
-                "{}",
-                codegen_enum(&pgninfo, &field, &field.enum_values)
-            )
-            .unwrap();
+            writeln!(message_file, "{}", codegen_enum(field, &field.enum_values)).unwrap();
+        }
+        if field.is_bitfield() {
+            writeln!(message_file, "{}", codegen_bitfield(field)).unwrap();
         }
     }
 
     let impl_tokens = codegen_impl(pgninfo);
     writeln!(message_file, "{}", impl_tokens).unwrap();
+
+    let test_tokens = codegen_known_vector_test(pgninfo);
+    writeln!(message_file, "{}", test_tokens).unwrap();
+}
+
+/// Raw value of the known vector test of the field at `index`. Numbers take values near the
+/// end of their range that sets the most bits, the negative one for signed fields, which the
+/// float types of scaled values still hold exactly. Others take alternating bits.
+fn known_raw_value(field: &Field, index: usize) -> i128 {
+    let step = (index % 7) as i128;
+    if !field.has_reserved_values() {
+        let mask = (1i128 << field.bit_length) - 1;
+        return 0x5555_5555_5555_5555 >> (index % 2) & mask;
+    }
+    let [min, max] = field.value_range();
+    let precision = match field.to_rust_type().map(|t| t.to_string()).as_deref() {
+        Some("f32") => 22,
+        Some("f64") => 48,
+        _ => 64,
+    };
+    if field.is_signed() {
+        min.max(-(1i128 << precision)) + step
+    } else {
+        max.min((1i128 << precision) - 1) - step
+    }
+}
+
+/// Test setting known values of the fields of the PGN, decoding the payload again and reading
+/// them back, then doing the same with each reserved value of every number. Strings and
+/// repeating groups are left to the known vectors in the tests of n2k-codegen.
+fn codegen_known_vector_test(pgninfo: &PgnInfo) -> TokenStream {
+    let struct_name = Ident::new(&type_name(&pgninfo.id), Span::call_site());
+    let optional = pgninfo.is_variable_length();
+    let wrap = |value: TokenStream| {
+        if optional {
+            quote! { Some(#value) }
+        } else {
+            value
+        }
+    };
+
+    let fields = accessor_fields(pgninfo.fixed_fields());
+    let getters: HashSet<_> = fields.iter().map(|(_, name)| name.to_owned()).collect();
+    let mut sets = vec![];
+    let mut checks = vec![];
+    let mut reserved_sets = [vec![], vec![], vec![]];
+    let mut reserved_checks = [vec![], vec![], vec![]];
+    for (index, (field, name)) in fields.iter().enumerate() {
+        let (raw_type, is_slice) = field.raw_type();
+        if is_slice
+            || field.match_value.is_some()
+            || pgninfo.variable_encoding(field).is_some()
+            || (field.is_signed() && !field.has_reserved_values())
+        {
+            continue;
+        }
+        let getter = Ident::new(name, Span::call_site());
+        let getter_raw = format_ident!("{}_raw", name);
+        let setter = setter_name(&getters, name);
+        let setter_raw = format_ident!("{}_raw", setter);
+        let setter = format_ident!("{}", setter);
+
+        let raw = known_raw_value(field, index);
+        let raw_literal = Literal::i128_unsuffixed(raw);
+        let expected_raw = wrap(quote! { #raw_literal });
+        checks.push(quote! {
+            assert_eq!(decoded.#getter_raw(), #expected_raw);
+        });
+
+        if field.is_enum() {
+            let enum_type = lookup_table_type(field);
+            let raw = TokenStream::from_str(&format!("{}{}", raw, raw_type)).unwrap();
+            let value = quote! { #enum_type::from(#raw) };
+            let expected = wrap(value.clone());
+            sets.push(quote! { message.#setter(#value); });
+            checks.push(quote! { assert_eq!(decoded.#getter(), #expected); });
+            continue;
+        }
+        if !field.has_reserved_values() {
+            sets.push(quote! { message.#setter_raw(#raw_literal); });
+            continue;
+        }
+
+        if field.is_date_time() {
+            let (value_type, constructor) = if field.n2k_type == "Date" {
+                (quote! { N2kDate }, quote! { from_days })
+            } else {
+                (quote! { N2kTime }, quote! { from_ticks })
+            };
+            let value = quote! { #value_type::#constructor(#raw_literal) };
+            let expected = wrap(quote! { N2kValue::Value(#value) });
+            sets.push(quote! { message.#setter(#value); });
+            checks.push(quote! { assert_eq!(decoded.#getter(), #expected); });
+        } else if field.is_float() {
+            let rust_type = field.to_rust_type().unwrap();
+            let value = (raw + field.offset as i128) as f64 * field.scale();
+            let tolerance = Literal::f64_unsuffixed(value.abs() * 1e-6 + field.scale());
+            let value = Literal::f64_suffixed(value);
+            let decoded_value = if optional {
+                quote! { decoded.#getter().unwrap() }
+            } else {
+                quote! { decoded.#getter() }
+            };
+            sets.push(quote! { message.#setter(#value as #rust_type); });
+            checks.push(quote! {
+                let value = #decoded_value.value().unwrap() as f64;
+                assert!((value - #value).abs() <= #tolerance);
+            });
+        } else {
+            let value = Literal::i128_unsuffixed(raw + field.offset as i128);
+            let expected = wrap(quote! { N2kValue::Value(#value) });
+            sets.push(quote! { message.#setter(#value); });
+            checks.push(quote! { assert_eq!(decoded.#getter(), #expected); });
+        }
+
+        let reserved = [
+            quote! { N2kValue::NotAvailable },
+            quote! { N2kValue::OutOfRange },
+            quote! { N2kValue::Reserved },
+        ];
+        for (i, (value, raw)) in reserved.iter().zip(field.reserved_values()).enumerate() {
+            let raw = Literal::i128_unsuffixed(raw);
+            let raw = wrap(quote! { #raw });
+            let expected = wrap(value.clone());
+            reserved_sets[i].push(quote! { message.#setter(#value); });
+            reserved_checks[i].push(quote! {
+                assert_eq!(decoded.#getter_raw(), #raw);
+                assert_eq!(decoded.#getter(), #expected);
+            });
+        }
+    }
+    if sets.is_empty() {
+        return quote! {};
+    }
+
+    let reserved_tests = reserved_sets
+        .iter()
+        .zip(&reserved_checks)
+        .filter(|(sets, _)| !sets.is_empty())
+        .map(|(sets, checks)| {
+            quote! {
+                let mut message = #struct_name::builder().build();
+                #(#sets)*
+                let decoded = #struct_name::try_from(message.as_bytes()).unwrap();
+                #(#checks)*
+            }
+        });
+
+    quote! {
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use core::convert::TryFrom;
+
+            #[test]
+            fn known_vectors() {
+                let mut message = #struct_name::builder().build();
+                #(#sets)*
+                let decoded = #struct_name::try_from(message.as_bytes()).unwrap();
+                assert_eq!(decoded.as_bytes(), message.as_bytes());
+                #(#checks)*
+
+                #(#reserved_tests)*
+            }
+        }
+    }
 }
 
-fn codegen_enum(pgninfo: &PgnInfo, field: &Field, values: &EnumValues) -> TokenStream {
+fn codegen_enum(field: &Field, values: &EnumValues) -> TokenStream {
     let enum_int_type = decode_unsigned_int_type_for_bit_length(field.bit_length).0;
-    let enum_type_name = lookup_table_type(&field);
+    let enum_type_name = lookup_table_type(field);
     let mut enum_fields = vec![];
     let mut enum_match_arms = vec![];
-    // Amazingly, the pgns.xml encodes some enum values as binary, others as decimal.
-    // Try to guess if it is in binary if all the values contain only 1 or 0.
-    let is_binary = values
-        .enum_values
-        .iter()
-        .all(|v| v.value.chars().all(|b| b == '0' || b == '1'));
-    for value in &values.enum_values {
-        let variant_name = Ident::new(&type_name(&value.name), Span::call_site());
-        let decoded_value = if is_binary {
-            usize::from_str_radix(&value.value, 2).unwrap().to_string()
-        } else {
-            value.value.to_owned()
+    let mut int_match_arms = vec![];
+    let mut names = vec![];
+    // `Other` holds unknown values
+    let mut seen_variants: HashMap<String, u32> = HashMap::new();
+    seen_variants.insert("Other".to_owned(), 1);
+    let mut seen_values = HashSet::new();
+    for (value, decoded_value) in values.enum_values.iter().zip(decoded_enum_values(values)) {
+        // Names such as "+1" and "-1" only differ in their sign
+        let name = type_name(&value.name.replace("+1", "plus 1").replace("-1", "minus 1"));
+        let name = match seen_variants.get_mut(&name) {
+            Some(counter) => {
+                *counter += 1;
+                format!("{}{}", name, counter)
+            }
+            None => {
+                seen_variants.insert(name.to_owned(), 1);
+                name
+            }
         };
+        let variant_name = Ident::new(&name, Span::call_site());
+        names.push((name.clone(), variant_name.clone()));
         let value = TokenStream::from_str(&decoded_value).unwrap();
         enum_fields.push(quote! {
           #variant_name
         });
 
-        enum_match_arms.push(quote! {
-            #value => Self::#variant_name
+        // Some lookup tables list a value twice, the first name wins when decoding
+        if seen_values.insert(decoded_value) {
+            enum_match_arms.push(quote! {
+                #value => Self::#variant_name
+            });
+        }
+
+        int_match_arms.push(quote! {
+            #enum_type_name::#variant_name => #value
         });
     }
 
-    // TODO: impl Into<>
+    let name_arms = names
+        .iter()
+        .map(|(name, variant)| quote! { Self::#variant => #name });
+    let from_name_arms = names
+        .iter()
+        .map(|(name, variant)| quote! { #name => Self::#variant });
     quote! {
-       #[derive(Debug)]
+       #[derive(Debug, Clone, Copy, PartialEq)]
        pub enum #enum_type_name {
            #(#enum_fields),*,
            Other(#enum_int_type)
@@ -388,154 +764,1720 @@ fn codegen_enum(pgninfo: &PgnInfo, field: &Field, values: &EnumValues) -> TokenS
                 }
             }
         }
-    }
-}
-
-fn codegen_impl(pgninfo: &PgnInfo) -> TokenStream {
-    let struct_name_str = type_name(&pgninfo.id);
-    let struct_name = Ident::new(&struct_name_str, Span::call_site());
-    let (getters, fields) = codegen_getters(pgninfo);
 
-    let field_debugs: Vec<_> = fields
-        .iter()
-        .map(|v| {
-            let ident = Ident::new(&v, Span::call_site());
-            quote! {
-                .field(#v, &self.#ident())
+        impl core::convert::From<#enum_type_name> for #enum_int_type {
+            #[inline(always)]
+            fn from(value: #enum_type_name) -> Self {
+                match value {
+                    #(#int_match_arms),*,
+                    #enum_type_name::Other(v) => v
+                }
             }
-        })
-        .collect();
-    quote! {
-        impl #struct_name {
-            #getters
         }
 
-        impl core::fmt::Debug for #struct_name {
-            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-                f.debug_struct(#struct_name_str)
-                #(#field_debugs)*
-                .finish()
+        impl #enum_type_name {
+            /// Name of the variant, None for `Other`
+            pub fn name(self) -> Option<&'static str> {
+                Some(match self {
+                    #(#name_arms),*,
+                    Self::Other(_) => return None
+                })
+            }
+
+            /// Variant named `name`
+            pub fn from_name(name: &str) -> Option<Self> {
+                Some(match name {
+                    #(#from_name_arms),*,
+                    _ => return None
+                })
             }
         }
+
+        #[cfg(feature = "serde")]
+        serde_lookup!(#enum_type_name, #enum_int_type);
     }
 }
 
-fn codegen_getters(pgninfo: &PgnInfo) -> (TokenStream, Vec<String>) {
-    let mut getters = vec![];
-    let mut generated_fields = vec![];
+/// Values of a lookup table in decimal. Amazingly, the pgns.xml encodes some enum values as
+/// binary, others as decimal. Try to guess if it is in binary if all the values contain only 1
+/// or 0.
+fn decoded_enum_values(values: &EnumValues) -> Vec<String> {
+    let is_binary = values
+        .enum_values
+        .iter()
+        .all(|v| v.value.chars().all(|b| b == '0' || b == '1'));
+    values
+        .enum_values
+        .iter()
+        .map(|value| {
+            if is_binary {
+                usize::from_str_radix(&value.value, 2).unwrap().to_string()
+            } else {
+                value.value.to_owned()
+            }
+        })
+        .collect()
+}
 
-    let mut seen_fields: HashMap<String, u32> = HashMap::new();
-    for field in &pgninfo.fields.fields {
-        if field.id == "reserved" {
+/// Flag set of a bitfield with a constant per named bit
+fn codegen_bitfield(field: &Field) -> TokenStream {
+    let int_type = decode_unsigned_int_type_for_bit_length(field.bit_length).0;
+    let type_name = lookup_table_type(field);
+    let mut consts = vec![];
+    let mut flags = vec![];
+    let mut seen_consts: HashMap<String, u32> = HashMap::new();
+    for value in &field.enum_bit_values.enum_bit_values {
+        if value.bit >= field.bit_length {
+            warn!(
+                "bit {} of {} is outside its {} bits",
+                value.bit, field.id, field.bit_length
+            );
             continue;
         }
-        // Handle duplicate field names by adding a counter to the end
-        let actual_field_name = if let Some(counter) = seen_fields.get(&field.id) {
-            format!("{}{}", field.id, counter)
-        } else {
-            field.id.to_owned()
+        // Handle duplicate names by adding a counter to the end
+        let name = const_name(&value.name);
+        let name = match seen_consts.get_mut(&name) {
+            Some(counter) => {
+                *counter += 1;
+                format!("{}_{}", name, counter)
+            }
+            None => {
+                seen_consts.insert(name.to_owned(), 1);
+                name
+            }
         };
-        // Increment counter
-        seen_fields
-            .entry(field.id.to_owned())
-            .and_modify(|v| *v += 1)
-            .or_insert(1);
-        let field_name = Ident::new(&field_name(&actual_field_name), Span::call_site());
-        let field_name_raw = Ident::new(&format!("{}_raw", field_name), Span::call_site());
-
-        getters.push(codegen_raw_get_impl(field, &field_name_raw));
-        // If a non-raw getter is available, use that as the main interpretation of it
-        if let Some(get) = codegen_get_impl(&pgninfo, field, &field_name_raw, &field_name) {
-            generated_fields.push(field_name.to_string());
-            getters.push(get);
-        } else {
-            generated_fields.push(field_name_raw.to_string());
-        }
+        let const_name = Ident::new(&name, Span::call_site());
+        let bit = value.bit;
+        let doc = &value.name;
+        consts.push(quote! {
+            #[doc = #doc]
+            pub const #const_name: Self = Self(1 << #bit);
+        });
+        flags.push(quote! {
+            (#doc, Self::#const_name)
+        });
     }
 
-    (
-        quote! {
-            #(#getters)*
-        },
-        generated_fields,
-    )
-}
+    quote! {
+        /// Set of flags, bits without a name are kept as they are
+        #[derive(Clone, Copy, PartialEq, Eq, Default)]
+        pub struct #type_name(#int_type);
 
-fn codegen_raw_get_impl(field: &Field, field_name: &Ident) -> TokenStream {
-    let (rust_type_raw, is_slice) = decode_unsigned_int_type_for_bit_length(field.bit_length);
+        impl #type_name {
+            #(#consts)*
 
-    let bit_offset = field.bit_offset;
-    let bit_length = field.bit_length;
-    let bit_end = bit_offset + bit_length;
+            /// Named flags and their names in canboat
+            pub const FLAGS: &'static [(&'static str, Self)] = &[#(#flags),*];
 
-    let bits = quote! {
-        self.raw.view_bits::<Lsb0>()[#bit_offset .. #bit_end]
-    };
-    if !is_slice {
-        if field.signed {
-            let signed_type = decode_signed_int_type_for_bit_length(field.bit_length);
-            quote! {
-                pub fn #field_name(&self) -> #signed_type {
-                    let value = #bits.load_be::<#rust_type_raw>();
-                    #signed_type::from_ne_bytes(value.to_ne_bytes())
-                }
+            pub const fn empty() -> Self {
+                Self(0)
             }
-        } else {
-            quote! {
-                pub fn #field_name(&self) -> #rust_type_raw {
-                    #bits.load_be::<#rust_type_raw>()
-                }
+
+            pub const fn from_bits(bits: #int_type) -> Self {
+                Self(bits)
             }
-        }
-    } else {
-        quote! {
-            pub fn #field_name<'a>(&'a self) -> #rust_type_raw {
-                #bits.as_raw_slice()
+
+            pub const fn bits(self) -> #int_type {
+                self.0
+            }
+
+            pub const fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+
+            /// Named flags that are set
+            pub fn iter(self) -> impl Iterator<Item = (&'static str, Self)> {
+                Self::FLAGS
+                    .iter()
+                    .copied()
+                    .filter(move |(_, flag)| self.contains(*flag))
             }
         }
-    }
-}
 
-fn codegen_get_impl(
-    pgninfo: &PgnInfo,
-    field: &Field,
-    field_name_raw: &Ident,
-    field_name: &Ident,
-) -> Option<TokenStream> {
-    let rust_type = field.to_rust_type();
+        impl core::ops::BitOr for #type_name {
+            type Output = Self;
 
-    Some(if field.is_string() {
-        // string
-        quote! {
-            pub fn #field_name<'a>(&'a self) -> Result<#rust_type, core::str::Utf8Error> {
-                core::str::from_utf8(self.#field_name_raw())
+            fn bitor(self, other: Self) -> Self {
+                Self(self.0 | other.0)
             }
         }
-    } else if field.is_enum() {
-        // lookup table
-        quote! {
-            pub fn #field_name(&self) -> #rust_type {
-                self.#field_name_raw().into()
+
+        impl core::ops::BitOrAssign for #type_name {
+            fn bitor_assign(&mut self, other: Self) {
+                self.0 |= other.0;
             }
         }
-    } else if field.is_float() {
-        let resolution = TokenStream::from_str(&field.resolution.to_string()).unwrap();
-        // float
-        quote! {
-            pub fn #field_name(&self) -> #rust_type {
-                (self.#field_name_raw() as #rust_type) * (#resolution as #rust_type)
+
+        impl core::convert::From<#int_type> for #type_name {
+            #[inline(always)]
+            fn from(value: #int_type) -> Self {
+                Self(value)
             }
         }
-    } else {
+
+        impl core::convert::From<#type_name> for #int_type {
+            #[inline(always)]
+            fn from(value: #type_name) -> Self {
+                value.0
+            }
+        }
+
+        impl core::fmt::Debug for #type_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                let mut set = f.debug_set();
+                set.entries(self.iter().map(|(name, _)| name));
+                let named = Self::FLAGS.iter().fold(0, |named, (_, flag)| named | flag.0);
+                let unnamed = self.0 & !named;
+                if unnamed != 0 {
+                    set.entry(&format_args!("{:#x}", unnamed));
+                }
+                set.finish()
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        serde_flags!(#type_name, #int_type);
+    }
+}
+
+fn codegen_impl(pgninfo: &PgnInfo) -> TokenStream {
+    let struct_name_str = type_name(&pgninfo.id);
+    let struct_name = Ident::new(&struct_name_str, Span::call_site());
+    let builder_name = format_ident!("{}Builder", struct_name_str);
+    let (bits_fns, bits_fn_names) = codegen_bits_fns(pgninfo);
+    let (getters, fields) = codegen_getters(pgninfo, pgninfo.fixed_fields(), None, &bits_fn_names);
+    let (setters, builder_methods, deserialize_arms) = codegen_setters(pgninfo, &bits_fn_names);
+    let (position, position_builder) = codegen_position(pgninfo).unwrap_or_default();
+    let (items, item_tokens) = match codegen_repeating(pgninfo) {
+        Some((items, item_tokens)) => (Some(items), item_tokens),
+        None => (None, quote! {}),
+    };
+    let (matches, match_presets) = codegen_match_fields(pgninfo);
+    let presets = codegen_presets(pgninfo);
+    let raw_mut = if match_presets.is_empty() && presets.is_empty() {
+        quote! {}
+    } else {
+        quote! { mut }
+    };
+    let pgn_id = TokenStream::from_str(&pgninfo.pgn.to_string()).unwrap();
+    let name = pgninfo.name();
+    let priority = format_ident!("Priority{}", pgninfo.priority());
+    let descriptor = codegen_descriptor(pgninfo);
+    let size = pgninfo.length;
+    // Variable length messages are built with their fixed fields and grow with the setters
+    let (new, bytes) = if pgninfo.is_variable_length() {
+        let capacity = pgninfo.capacity();
+        let len = pgninfo.fixed_length().min(capacity);
+        (
+            quote! {
+                let #raw_mut raw = [0xff; #capacity];
+                #match_presets
+                #presets
+                Self { raw, len: #len }
+            },
+            quote! { &self.raw[..self.len] },
+        )
+    } else {
+        (
+            quote! {
+                let #raw_mut raw = [0xff; #size];
+                #match_presets
+                #presets
+                Self { raw }
+            },
+            quote! { &self.raw },
+        )
+    };
+
+    let serialize = codegen_serialize(
+        quote! {},
+        quote! { #struct_name },
+        pgninfo.fixed_fields(),
+        &fields,
+        pgninfo.is_variable_length(),
+        items.is_some(),
+    );
+
+    let mut field_debugs: Vec<_> = fields
+        .iter()
+        .map(|v| {
+            let ident = Ident::new(v, Span::call_site());
+            quote! {
+                .field(#v, &self.#ident())
+            }
+        })
+        .collect();
+    if items.is_some() {
+        field_debugs.push(quote! {
+            .field("items", &self.items())
+        });
+    }
+    quote! {
+        impl #struct_name {
+            pub const PGN: u32 = #pgn_id;
+
+            /// Name of the PGN in canboat
+            pub const NAME: &'static str = #name;
+
+            pub const PRIORITY: n2k::Priority = n2k::Priority::#priority;
+
+            /// Layout of the PGN as canboat describes it
+            pub const DESCRIPTOR: PgnDescriptor = #descriptor;
+
+            /// Starts with every bit set, reserved fields are filled with ones and fields that
+            /// are not set read as "not available". Match fields are set to this variant and
+            /// variable length strings are empty.
+            pub fn builder() -> #builder_name {
+                #builder_name {
+                    inner: { #new },
+                }
+            }
+
+            #matches
+
+            pub fn as_bytes(&self) -> &[u8] {
+                #bytes
+            }
+
+            /// Message with this payload, `id` has to carry `Self::PGN`
+            pub fn to_message(&self, id: n2k::Id) -> Result<n2k::Message<'_>, n2k::MessageError> {
+                n2k::Message::new(id, self.as_bytes())
+            }
+
+            #bits_fns
+
+            #getters
+
+            #items
+
+            #setters
+
+            #position
+        }
+
+        #item_tokens
+
+        pub struct #builder_name {
+            inner: #struct_name,
+        }
+
+        impl #builder_name {
+            #builder_methods
+
+            #position_builder
+
+            pub fn build(self) -> #struct_name {
+                self.inner
+            }
+        }
+
+        impl core::fmt::Debug for #struct_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(#struct_name_str)
+                #(#field_debugs)*
+                .finish()
+            }
+        }
+
+        #serialize
+
+        /// Builds the message from the values of the builder methods named like the keys,
+        /// others such as the repeating groups are ignored
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for #struct_name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = #struct_name;
+
+                    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                        f.write_str(#struct_name_str)
+                    }
+
+                    #[allow(unused_mut)]
+                    fn visit_map<A: serde::de::MapAccess<'de>>(
+                        self,
+                        mut map: A,
+                    ) -> Result<#struct_name, A::Error> {
+                        let mut builder = #struct_name::builder();
+                        while let Some(key) = map.next_key::<SerdeBuf>()? {
+                            builder = match key.as_str() {
+                                #deserialize_arms
+                                _ => {
+                                    map.next_value::<serde::de::IgnoredAny>()?;
+                                    builder
+                                }
+                            };
+                        }
+                        Ok(builder.build())
+                    }
+                }
+
+                deserializer.deserialize_map(Visitor)
+            }
+        }
+    }
+}
+
+/// `matches` telling this variant of a PGN from the others, and the statements setting its
+/// match fields in a `raw` payload
+fn codegen_match_fields(pgninfo: &PgnInfo) -> (TokenStream, TokenStream) {
+    let mut checks = vec![];
+    let mut presets = vec![];
+    let mut end = 0;
+    for field in &pgninfo.fields.fields {
+        let (rust_type_raw, is_slice) = decode_unsigned_int_type_for_bit_length(field.bit_length);
+        let value = match field.match_value {
+            Some(value) if !is_slice => TokenStream::from_str(&value.to_string()).unwrap(),
+            _ => continue,
+        };
+        let bit_offset = field.bit_offset;
+        let bit_end = field.bit_offset + field.bit_length;
+        end = end.max(bit_end);
+
+        checks.push(quote! {
+            bits[#bit_offset .. #bit_end].load_le::<#rust_type_raw>() == #value
+        });
+        presets.push(quote! {
+            raw.view_bits_mut::<Lsb0>()[#bit_offset .. #bit_end].store_le::<#rust_type_raw>(#value);
+        });
+    }
+
+    let matches = if checks.is_empty() {
+        quote! {
+            /// Whether `payload` is this variant of `Self::PGN`, which has no match fields
+            pub fn matches(_payload: &[u8]) -> bool {
+                true
+            }
+        }
+    } else {
+        quote! {
+            /// Whether `payload` is this variant of `Self::PGN`, going by its match fields
+            pub fn matches(payload: &[u8]) -> bool {
+                let bits = payload.view_bits::<Lsb0>();
+                bits.len() >= #end #(&& #checks)*
+            }
+        }
+    };
+    (
+        matches,
+        quote! {
+            #(#presets)*
+        },
+    )
+}
+
+/// `items` iterating over the repeating group of a PGN and the item type exposing the getters
+/// of one group
+fn codegen_repeating(pgninfo: &PgnInfo) -> Option<(TokenStream, TokenStream)> {
+    let fields = pgninfo.repeating_group();
+    if pgninfo.has_variable_fields() {
+        info!(
+            "repeating group of pgn {} follows a variable length string",
+            pgninfo.pgn
+        );
+        return None;
+    }
+    let group_offset = fields.first()?.bit_offset;
+    let group_length = pgninfo.repeating_bit_length()?;
+
+    let struct_name_str = type_name(&pgninfo.id);
+    let item_name_str = format!("{}Item", struct_name_str);
+    let item_name = Ident::new(&item_name_str, Span::call_site());
+    let (getters, item_fields) =
+        codegen_getters(pgninfo, fields, Some(group_offset), &HashMap::new());
+    let serialize = codegen_serialize(
+        quote! { <'m> },
+        quote! { #item_name<'m> },
+        fields,
+        &item_fields,
+        false,
+        false,
+    );
+    let field_debugs = item_fields.iter().map(|v| {
+        let ident = Ident::new(v, Span::call_site());
+        quote! {
+            .field(#v, &self.#ident())
+        }
+    });
+
+    // The count field precedes the group, without one every group that fits is returned
+    let count = match pgninfo.count_field() {
+        Some(count_field) => {
+            let name = accessor_fields(pgninfo.fixed_fields())
+                .into_iter()
+                .find(|(field, _)| std::ptr::eq(*field, count_field))
+                .map(|(_, name)| format_ident!("{}_raw", name))?;
+            if pgninfo.is_variable_length() {
+                quote! { self.#name().map_or(0, |count| count as usize) }
+            } else {
+                quote! { self.#name() as usize }
+            }
+        }
+        None => quote! { usize::MAX },
+    };
+
+    let items = quote! {
+        /// Repeating groups of fields
+        pub fn items(&self) -> Repeated<'_, #item_name<'_>> {
+            Repeated::new(self.as_bytes(), #group_offset, #count)
+        }
+    };
+    let item_tokens = quote! {
+        #[derive(Clone, Copy)]
+        pub struct #item_name<'m> {
+            raw: &'m [u8],
+            offset: usize,
+        }
+
+        impl<'m> RepeatingGroup<'m> for #item_name<'m> {
+            const BIT_LENGTH: usize = #group_length;
+
+            fn at(raw: &'m [u8], offset: usize) -> Self {
+                Self { raw, offset }
+            }
+        }
+
+        impl<'m> #item_name<'m> {
+            #getters
+        }
+
+        impl<'m> core::fmt::Debug for #item_name<'m> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(#item_name_str)
+                #(#field_debugs)*
+                .finish()
+            }
+        }
+
+        #serialize
+    };
+    Some((items, item_tokens))
+}
+
+/// Fields with accessors and their names, reserved fields are skipped
+fn accessor_fields(fields: &[Field]) -> Vec<(&Field, String)> {
+    let mut accessors = vec![];
+    let mut seen_fields: HashMap<String, u32> = HashMap::new();
+    for field in fields {
+        if field.id == "reserved" {
+            continue;
+        }
+        // Handle duplicate field names by adding a counter to the end
+        let actual_field_name = if let Some(counter) = seen_fields.get(&field.id) {
+            format!("{}{}", field.id, counter)
+        } else {
+            field.id.to_owned()
+        };
+        // Increment counter
+        seen_fields
+            .entry(field.id.to_owned())
+            .and_modify(|v| *v += 1)
+            .or_insert(1);
+        accessors.push((field, field_name(&actual_field_name)));
+    }
+    accessors
+}
+
+/// Private `<field>_bits` functions locating variable length strings and the fields after them
+/// at runtime, and their names by index of the `fixed_fields`
+fn codegen_bits_fns(pgninfo: &PgnInfo) -> (TokenStream, HashMap<usize, Ident>) {
+    let fixed = pgninfo.fixed_fields();
+    let positions = pgninfo.field_positions();
+    let mut bits_fns = vec![];
+    let mut names = HashMap::new();
+    for (field, name) in accessor_fields(fixed) {
+        let index = fixed.iter().position(|f| std::ptr::eq(f, field)).unwrap();
+        let encoding = pgninfo.variable_encoding(field);
+        let start = match positions[index] {
+            FieldPosition::After(string, bits) => {
+                // Strings are never reserved and always have accessors
+                let string_bits = &names[&string];
+                quote! { self.#string_bits()?.end + #bits }
+            }
+            FieldPosition::Fixed(_) if encoding.is_none() => continue,
+            FieldPosition::Fixed(bit_offset) => quote! { #bit_offset },
+        };
+        let end = match encoding {
+            Some(encoding) => {
+                let encoding = encoding.tokens();
+                quote! { start + #encoding.field_len(self.as_bytes().get(start / 8..)?)? * 8 }
+            }
+            None => {
+                let bit_length = field.bit_length;
+                quote! { start + #bit_length }
+            }
+        };
+        let bits_fn = format_ident!("{}_bits", name);
+        bits_fns.push(quote! {
+            fn #bits_fn(&self) -> Option<core::ops::Range<usize>> {
+                let start = #start;
+                Some(start..#end)
+            }
+        });
+        names.insert(index, bits_fn);
+    }
+    (
+        quote! {
+            #(#bits_fns)*
+        },
+        names,
+    )
+}
+
+/// Statements writing empty variable length strings into a `raw` payload, and "not available"
+/// into signed fields as all bits set only means that for unsigned ones
+fn codegen_presets(pgninfo: &PgnInfo) -> TokenStream {
+    let size = if pgninfo.is_variable_length() {
+        pgninfo.capacity()
+    } else {
+        pgninfo.length
+    };
+    let presets = pgninfo
+        .fixed_fields()
+        .iter()
+        .zip(pgninfo.empty_layout())
+        .filter(|(_, bits)| bits.end <= size * 8)
+        .filter_map(|(field, bits)| {
+            if let Some(encoding) = pgninfo.variable_encoding(field) {
+                let encoding = encoding.tokens();
+                let start = bits.start / 8;
+                let end = bits.end / 8;
+                return Some(quote! {
+                    raw[#start..#end].copy_from_slice(#encoding.empty());
+                });
+            }
+            if !field.is_signed() || !field.has_reserved_values() {
+                return None;
+            }
+            let (rust_type_raw, _) = field.raw_type();
+            let not_available = Literal::i128_unsuffixed(field.reserved_values()[0]);
+            let start = bits.start;
+            let end = bits.end;
+            Some(quote! {
+                raw.view_bits_mut::<Lsb0>()[#start..#end].store_le::<#rust_type_raw>(#not_available);
+            })
+        });
+    quote! {
+        #(#presets)*
+    }
+}
+
+/// Range of the bits of `field` in `self.raw`. Fields of a repeating group starting at bit
+/// `group_offset` are relative to the `self.offset` of the group.
+fn bit_range(field: &Field, group_offset: Option<usize>) -> TokenStream {
+    match group_offset {
+        None => {
+            let bit_offset = field.bit_offset;
+            let bit_end = bit_offset + field.bit_length;
+            quote! { #bit_offset .. #bit_end }
+        }
+        Some(group_offset) => {
+            let bit_offset = field.bit_offset - group_offset;
+            let bit_end = bit_offset + field.bit_length;
+            quote! { self.offset + #bit_offset .. self.offset + #bit_end }
+        }
+    }
+}
+
+fn codegen_getters(
+    pgninfo: &PgnInfo,
+    fields: &[Field],
+    group_offset: Option<usize>,
+    bits_fns: &HashMap<usize, Ident>,
+) -> (TokenStream, Vec<String>) {
+    let mut getters = vec![];
+    let mut generated_fields = vec![];
+
+    // Groups are only iterated over when received completely
+    let optional = pgninfo.is_variable_length() && group_offset.is_none();
+    for (field, name) in accessor_fields(fields) {
+        let field_name = Ident::new(&name, Span::call_site());
+        let field_name_raw = format_ident!("{}_raw", name);
+
+        // Fields after variable length strings are located at runtime
+        let index = fields.iter().position(|f| std::ptr::eq(f, field)).unwrap();
+        let range = match bits_fns.get(&index) {
+            Some(bits_fn) => quote! { self.#bits_fn()? },
+            None => bit_range(field, group_offset),
+        };
+        let raw_get = codegen_raw_get_impl(field, &field_name_raw, range, optional);
+        // If a non-raw getter is available, use that as the main interpretation of it
+        let (getter, get) =
+            match codegen_get_impl(pgninfo, field, &field_name_raw, &field_name, optional) {
+                Some(get) => {
+                    getters.push(raw_get);
+                    (field_name, get)
+                }
+                None => (field_name_raw, raw_get),
+            };
+        generated_fields.push(getter.to_string());
+
+        match field.unit() {
+            Some(unit) => {
+                let doc = format!("{} in {}", field.name, unit);
+                let unit_const = format_ident!("{}_UNIT", name.to_uppercase());
+                getters.push(quote! {
+                    pub const #unit_const: &'static str = #unit;
+
+                    #[doc = #doc]
+                    #get
+                });
+                getters.push(codegen_quantity_impl(field, &getter, optional));
+            }
+            None => getters.push(get),
+        }
+    }
+
+    (
+        quote! {
+            #(#getters)*
+        },
+        generated_fields,
+    )
+}
+
+/// Getter of the value of `getter` as a `uom` quantity, nothing if the value is no number or
+/// its unit is not known
+fn codegen_quantity_impl(field: &Field, getter: &Ident, optional: bool) -> TokenStream {
+    let (_, is_slice) = field.raw_type();
+    if is_slice
+        || field.is_enum()
+        || field.is_bitfield()
+        || field.is_date_time()
+        || field.is_string()
+        || field.string_encoding().is_some()
+    {
+        return quote! {};
+    }
+    let (quantity, unit) = match field.unit().and_then(uom_quantity) {
+        Some(quantity) => quantity,
+        None => return quote! {},
+    };
+
+    let name = format_ident!("{}_quantity", getter.to_string().trim_end_matches("_raw"));
+    let quantity = quote! { uom::si::f32::#quantity };
+    let new = quote! { #quantity::new::<uom::si::#unit>(value as f32) };
+    if field.has_reserved_values() {
+        return if optional {
+            quote! {
+                #[cfg(feature = "uom")]
+                pub fn #name(&self) -> Option<N2kValue<#quantity>> {
+                    self.#getter().map(|value| value.map(|value| #new))
+                }
+            }
+        } else {
+            quote! {
+                #[cfg(feature = "uom")]
+                pub fn #name(&self) -> N2kValue<#quantity> {
+                    self.#getter().map(|value| #new)
+                }
+            }
+        };
+    }
+    if optional {
+        quote! {
+            #[cfg(feature = "uom")]
+            pub fn #name(&self) -> Option<#quantity> {
+                self.#getter().map(|value| #new)
+            }
+        }
+    } else {
+        quote! {
+            #[cfg(feature = "uom")]
+            pub fn #name(&self) -> #quantity {
+                let value = self.#getter();
+                #new
+            }
+        }
+    }
+}
+
+/// `uom` quantity and unit of the canboat `unit`
+fn uom_quantity(unit: &str) -> Option<(TokenStream, TokenStream)> {
+    Some(match unit {
+        "rad" => (quote! { Angle }, quote! { angle::radian }),
+        "deg" => (quote! { Angle }, quote! { angle::degree }),
+        "s" | "seconds" => (quote! { Time }, quote! { time::second }),
+        "minutes" => (quote! { Time }, quote! { time::minute }),
+        "days" => (quote! { Time }, quote! { time::day }),
+        "m" => (quote! { Length }, quote! { length::meter }),
+        "m/s" => (quote! { Velocity }, quote! { velocity::meter_per_second }),
+        "rad/s" => (
+            quote! { AngularVelocity },
+            quote! { angular_velocity::radian_per_second },
+        ),
+        "rpm" => (
+            quote! { AngularVelocity },
+            quote! { angular_velocity::revolution_per_minute },
+        ),
+        "K" => (
+            quote! { ThermodynamicTemperature },
+            quote! { thermodynamic_temperature::kelvin },
+        ),
+        "C" => (
+            quote! { ThermodynamicTemperature },
+            quote! { thermodynamic_temperature::degree_celsius },
+        ),
+        "V" => (
+            quote! { ElectricPotential },
+            quote! { electric_potential::volt },
+        ),
+        "A" => (
+            quote! { ElectricCurrent },
+            quote! { electric_current::ampere },
+        ),
+        "W" => (quote! { Power }, quote! { power::watt }),
+        "kWh" => (quote! { Energy }, quote! { energy::kilowatt_hour }),
+        "Hz" => (quote! { Frequency }, quote! { frequency::hertz }),
+        "kHz" => (quote! { Frequency }, quote! { frequency::kilohertz }),
+        "hPa" => (quote! { Pressure }, quote! { pressure::hectopascal }),
+        "kPa" => (quote! { Pressure }, quote! { pressure::kilopascal }),
+        "dPa" => (quote! { Pressure }, quote! { pressure::decipascal }),
+        "Tesla" => (
+            quote! { MagneticFluxDensity },
+            quote! { magnetic_flux_density::tesla },
+        ),
+        "L" => (quote! { Volume }, quote! { volume::liter }),
+        "%" => (quote! { Ratio }, quote! { ratio::percent }),
+        "ppt" => (quote! { Ratio }, quote! { ratio::part_per_thousand }),
+        "ppm" => (quote! { Ratio }, quote! { ratio::part_per_million }),
+        _ => return None,
+    })
+}
+
+/// Raw getter, `optional` ones return None for fields beyond the received length
+fn codegen_raw_get_impl(
+    field: &Field,
+    field_name: &Ident,
+    range: TokenStream,
+    optional: bool,
+) -> TokenStream {
+    let (rust_type_raw, is_slice) = field.raw_type();
+
+    let (return_type, value) = if !is_slice {
+        if field.is_signed() {
+            let signed_type = decode_signed_int_type_for_bit_length(field.bit_length);
+            // Shift the sign bit of shorter fields into place, the arithmetic shift back
+            // extends it
+            let shift = int_bit_length(field.bit_length) - field.bit_length;
+            let value = if shift == 0 {
+                quote! { bits.load_le::<#rust_type_raw>() as #signed_type }
+            } else {
+                quote! { ((bits.load_le::<#rust_type_raw>() << #shift) as #signed_type) >> #shift }
+            };
+            (signed_type, value)
+        } else {
+            (
+                rust_type_raw.clone(),
+                quote! { bits.load_le::<#rust_type_raw>() },
+            )
+        }
+    } else {
+        (rust_type_raw, quote! { bits.as_raw_slice() })
+    };
+    let (lifetime, receiver) = if is_slice {
+        (quote! { <'a> }, quote! { &'a self })
+    } else {
+        (quote! {}, quote! { &self })
+    };
+
+    if optional {
+        quote! {
+            pub fn #field_name #lifetime(#receiver) -> Option<#return_type> {
+                let bits = self.raw.view_bits::<Lsb0>()[..self.len * 8].get(#range)?;
+                Some(#value)
+            }
+        }
+    } else {
+        quote! {
+            pub fn #field_name #lifetime(#receiver) -> #return_type {
+                let bits = &self.raw.view_bits::<Lsb0>()[#range];
+                #value
+            }
+        }
+    }
+}
+
+/// Typed getter interpreting the raw value, `optional` ones return None for fields beyond the
+/// received length
+fn codegen_get_impl(
+    pgninfo: &PgnInfo,
+    field: &Field,
+    field_name_raw: &Ident,
+    field_name: &Ident,
+    optional: bool,
+) -> Option<TokenStream> {
+    let rust_type = field.to_rust_type()?;
+
+    if let Some(encoding) = field.string_encoding() {
+        // variable length string, the raw getter returns the whole field
+        if !optional {
+            info!(
+                "variable length string {} in single frame pgn {}",
+                field.id, pgninfo.pgn
+            );
+            return None;
+        }
+        let encoding = encoding.tokens();
+        return Some(quote! {
+            pub fn #field_name<'a>(&'a self) -> Option<#rust_type> {
+                self.#field_name_raw().and_then(|raw| #encoding.decode(raw))
+            }
+        });
+    }
+
+    // type and the expression converting `raw` to it
+    let (return_type, value) = if field.is_string() {
+        // string
+        (
+            quote! { Result<#rust_type, core::str::Utf8Error> },
+            quote! { core::str::from_utf8(trim_padding(raw)) },
+        )
+    } else if field.is_date_time() {
+        // date or time, the raw value is already in the unit of the type
+        let constructor = if field.n2k_type == "Date" {
+            quote! { from_days }
+        } else {
+            quote! { from_ticks }
+        };
+        (
+            rust_type.clone(),
+            quote! { #rust_type::#constructor(raw.into()) },
+        )
+    } else if field.is_enum() || field.is_bitfield() {
+        // lookup table or flags
+        (rust_type, quote! { raw.into() })
+    } else if field.is_float() && field.bit_length <= 64 {
+        let resolution = TokenStream::from_str(&field.resolution.to_string()).unwrap();
+        // float, the offset is applied to the integer so it keeps its precision
+        let raw = if field.offset != 0 {
+            let offset = field.offset;
+            quote! { (raw as i64 + #offset) }
+        } else {
+            quote! { raw }
+        };
+        (
+            rust_type.clone(),
+            quote! { (#raw as #rust_type) * (#resolution as #rust_type) },
+        )
+    } else if field.offset != 0 && field.bit_length <= 32 {
+        let offset = field.offset;
+        // integer with an offset
+        (rust_type, quote! { raw as i64 + #offset })
+    } else if field.is_plain_integer() {
+        // integer, only the reserved values need interpreting
+        (field.int_type(), quote! { raw })
+    } else {
         info!(
             "unhandled non-raw field {:?} for pgn {}",
             field, pgninfo.pgn
         );
         return None;
-    })
+    };
+    // Reserved raw values are told apart from the value
+    let (return_type, value) = if field.has_reserved_values() {
+        let [not_available, out_of_range, reserved] =
+            field.reserved_values().map(Literal::i128_unsuffixed);
+        (
+            quote! { N2kValue<#return_type> },
+            quote! {
+                match raw {
+                    #not_available => N2kValue::NotAvailable,
+                    #out_of_range => N2kValue::OutOfRange,
+                    #reserved => N2kValue::Reserved,
+                    raw => N2kValue::Value(#value),
+                }
+            },
+        )
+    } else {
+        (return_type, value)
+    };
+    let (lifetime, receiver) = if field.is_string() {
+        (quote! { <'a> }, quote! { &'a self })
+    } else {
+        (quote! {}, quote! { &self })
+    };
+
+    Some(if optional {
+        quote! {
+            pub fn #field_name #lifetime(#receiver) -> Option<#return_type> {
+                self.#field_name_raw().map(|raw| #value)
+            }
+        }
+    } else {
+        quote! {
+            pub fn #field_name #lifetime(#receiver) -> #return_type {
+                let raw = self.#field_name_raw();
+                #value
+            }
+        }
+    })
+}
+
+/// Setters writing each field back into the bit layout and the builder methods calling them
+fn codegen_setters(
+    pgninfo: &PgnInfo,
+    bits_fns: &HashMap<usize, Ident>,
+) -> (TokenStream, TokenStream, TokenStream) {
+    let mut setters = vec![];
+    let mut builder_methods = vec![];
+    let mut deserialize_arms = vec![];
+
+    let fixed = pgninfo.fixed_fields();
+    let positions = pgninfo.field_positions();
+    let fields = accessor_fields(fixed);
+    let getters: HashSet<_> = fields.iter().map(|(_, name)| name.to_owned()).collect();
+    for (field, name) in &fields {
+        let setter = setter_name(&getters, name);
+        let setter_raw = format_ident!("{}_raw", setter);
+        let setter = format_ident!("{}", setter);
+        let index = fixed.iter().position(|f| std::ptr::eq(f, *field)).unwrap();
+
+        if let Some(encoding) = pgninfo.variable_encoding(field) {
+            // Variable length strings move the fields after them
+            let start = match positions[index] {
+                FieldPosition::Fixed(bit_offset) => quote! { Some(#bit_offset) },
+                FieldPosition::After(string, bits) => {
+                    let string_bits = &bits_fns[&string];
+                    quote! { self.#string_bits().map(|bits| bits.end + #bits) }
+                }
+            };
+            setters.push(codegen_string_set_impl(
+                pgninfo,
+                encoding,
+                start,
+                &setter_raw,
+                &setter,
+            ));
+            let method = format_ident!("{}", name);
+            builder_methods.push(quote! {
+                pub fn #method(mut self, value: &str) -> Self {
+                    self.inner.#setter(value);
+                    self
+                }
+            });
+            deserialize_arms.push(codegen_deserialize_arm(
+                &method,
+                quote! { SerdeBuf },
+                quote! { value.as_str() },
+            ));
+            continue;
+        }
+
+        let (raw_set, raw_type) =
+            codegen_raw_set_impl(pgninfo, field, &setter_raw, bits_fns.get(&index));
+        setters.push(raw_set);
+
+        // Builders use the same interpretation as the getters
+        let (method, setter, value_type, de_type) =
+            match codegen_set_impl(field, &setter_raw, &setter) {
+                Some((set, value_type, de_type)) => {
+                    setters.push(set);
+                    (format_ident!("{}", name), setter, value_type, de_type)
+                }
+                None => (
+                    format_ident!("{}_raw", name),
+                    setter_raw,
+                    raw_type.clone(),
+                    raw_type,
+                ),
+            };
+        builder_methods.push(quote! {
+            pub fn #method(mut self, value: #value_type) -> Self {
+                self.inner.#setter(value);
+                self
+            }
+        });
+        // Text and bytes are borrowed by the setters
+        let (de_type, value) = match de_type.to_string().as_str() {
+            "& str" => (quote! { SerdeBuf }, quote! { value.as_str() }),
+            "& [u8]" => (quote! { SerdeBuf }, quote! { value.as_bytes() }),
+            _ => (de_type, quote! { value }),
+        };
+        deserialize_arms.push(codegen_deserialize_arm(&method, de_type, value));
+    }
+
+    (
+        quote! {
+            #(#setters)*
+        },
+        quote! {
+            #(#builder_methods)*
+        },
+        quote! {
+            #(#deserialize_arms)*
+        },
+    )
+}
+
+/// Name of the setter of the field `name`. Fields such as "setTemperature" already have getters
+/// named like setters.
+fn setter_name(getters: &HashSet<String>, name: &str) -> String {
+    if getters.contains(&format!("set_{}", name)) {
+        format!("set_{}_value", name)
+    } else {
+        format!("set_{}", name)
+    }
+}
+
+/// `PgnDescriptor` of the PGN, fields after a variable length string have no fixed offset
+fn codegen_descriptor(pgninfo: &PgnInfo) -> TokenStream {
+    let fields = &pgninfo.fields.fields;
+    let repeating_fields = pgninfo.repeating_fields.min(fields.len());
+    let mut positions: Vec<_> = pgninfo
+        .field_positions()
+        .into_iter()
+        .map(|position| match position {
+            FieldPosition::Fixed(bit_offset) => Some(bit_offset),
+            FieldPosition::After(..) => None,
+        })
+        .collect();
+    let moved = pgninfo.has_variable_fields();
+    positions.extend(
+        fields[positions.len()..]
+            .iter()
+            .map(|field| Some(field.bit_offset).filter(|_| !moved)),
+    );
+
+    let field_descriptors = fields.iter().zip(positions).map(|(field, bit_offset)| {
+        let id = &field.id;
+        let name = &field.name;
+        let kind = &field.n2k_type;
+        let bit_offset = match bit_offset {
+            Some(bit_offset) => quote! { Some(#bit_offset) },
+            None => quote! { None },
+        };
+        let bit_length = field.bit_length;
+        let signed = field.is_signed();
+        let resolution = Literal::f64_unsuffixed(field.scale());
+        let offset = field.offset;
+        let unit = match field.unit() {
+            Some(unit) => quote! { Some(#unit) },
+            None => quote! { None },
+        };
+        let lookup = field
+            .enum_values
+            .enum_values
+            .iter()
+            .zip(decoded_enum_values(&field.enum_values))
+            .map(|(pair, value)| {
+                let value = Literal::u32_unsuffixed(value.parse().unwrap());
+                let name = &pair.name;
+                quote! { (#value, #name) }
+            });
+        let bits = field.enum_bit_values.enum_bit_values.iter().map(|pair| {
+            let bit = Literal::u8_unsuffixed(pair.bit as u8);
+            let name = &pair.name;
+            quote! { (#bit, #name) }
+        });
+        let match_value = match field.match_value {
+            Some(value) => quote! { Some(#value) },
+            None => quote! { None },
+        };
+        quote! {
+            FieldDescriptor {
+                id: #id,
+                name: #name,
+                kind: #kind,
+                bit_offset: #bit_offset,
+                bit_length: #bit_length,
+                signed: #signed,
+                resolution: #resolution,
+                offset: #offset,
+                unit: #unit,
+                lookup: &[#(#lookup),*],
+                bits: &[#(#bits),*],
+                match_value: #match_value,
+            }
+        }
+    });
+
+    let pgn = pgninfo.pgn;
+    let id = &pgninfo.id;
+    let name = pgninfo.name();
+    let priority = format_ident!("Priority{}", pgninfo.priority());
+    let fast_packet = pgninfo.xtype == "Fast";
+    let length = pgninfo.length;
+    quote! {
+        PgnDescriptor {
+            pgn: #pgn,
+            id: #id,
+            name: #name,
+            priority: n2k::Priority::#priority,
+            fast_packet: #fast_packet,
+            length: #length,
+            repeating_fields: #repeating_fields,
+            fields: &[#(#field_descriptors),*],
+        }
+    }
+}
+
+/// Getter and setter of the latitude and longitude of PGNs with one of each as a `GeoPosition`,
+/// and the builder method
+fn codegen_position(pgninfo: &PgnInfo) -> Option<(TokenStream, TokenStream)> {
+    let fields = accessor_fields(pgninfo.fixed_fields());
+    let find = |n2k_type: &str| {
+        let mut matching = fields
+            .iter()
+            .filter(|(field, _)| field.n2k_type == n2k_type && field.has_reserved_values());
+        match (matching.next(), matching.next()) {
+            (Some((_, name)), None) => Some(name.to_owned()),
+            _ => None,
+        }
+    };
+    let latitude = find("Latitude")?;
+    let longitude = find("Longitude")?;
+    let getters: HashSet<_> = fields.iter().map(|(_, name)| name.to_owned()).collect();
+    if getters.contains("position") || getters.contains("set_position") {
+        info!("pgn {} has a field named position", pgninfo.pgn);
+        return None;
+    }
+    let set_latitude = format_ident!("{}", setter_name(&getters, &latitude));
+    let set_longitude = format_ident!("{}", setter_name(&getters, &longitude));
+    let latitude = format_ident!("{}", latitude);
+    let longitude = format_ident!("{}", longitude);
+
+    let getter = if pgninfo.is_variable_length() {
+        quote! {
+            /// Latitude and longitude, not available unless both are
+            pub fn position(&self) -> Option<N2kValue<GeoPosition>> {
+                Some(
+                    self.#latitude()?
+                        .zip(self.#longitude()?)
+                        .map(|(latitude, longitude)| GeoPosition::new(latitude, longitude))
+                )
+            }
+        }
+    } else {
+        quote! {
+            /// Latitude and longitude, not available unless both are
+            pub fn position(&self) -> N2kValue<GeoPosition> {
+                self.#latitude()
+                    .zip(self.#longitude())
+                    .map(|(latitude, longitude)| GeoPosition::new(latitude, longitude))
+            }
+        }
+    };
+    Some((
+        quote! {
+            #getter
+
+            pub fn set_position(&mut self, value: impl Into<N2kValue<GeoPosition>>) {
+                let value = value.into();
+                self.#set_latitude(value.map(|position| position.latitude));
+                self.#set_longitude(value.map(|position| position.longitude));
+            }
+        },
+        quote! {
+            pub fn position(mut self, value: impl Into<N2kValue<GeoPosition>>) -> Self {
+                self.inner.set_position(value);
+                self
+            }
+        },
+    ))
+}
+
+/// Match arm of a `Deserialize` impl passing the value of the key named like the builder
+/// `method` to it
+fn codegen_deserialize_arm(
+    method: &Ident,
+    de_type: TokenStream,
+    value: TokenStream,
+) -> TokenStream {
+    let key = method.to_string();
+    quote! {
+        #key => {
+            let value = map.next_value::<#de_type>()?;
+            builder.#method(#value)
+        }
+    }
+}
+
+/// `Serialize` impl of a message or repeating group `ty`, the values of the getters of
+/// `fields` named like them. `optional` getters return an `Option`.
+fn codegen_serialize(
+    generics: TokenStream,
+    ty: TokenStream,
+    fields: &[Field],
+    getters: &[String],
+    optional: bool,
+    items: bool,
+) -> TokenStream {
+    let mut serialize_fields: Vec<_> = accessor_fields(fields)
+        .into_iter()
+        .zip(getters)
+        .map(|((field, _), getter)| {
+            let ident = Ident::new(getter, Span::call_site());
+            // Invalid UTF-8 serializes as none
+            let value = if field.is_string() && !getter.ends_with("_raw") {
+                if optional {
+                    quote! { self.#ident().and_then(Result::ok) }
+                } else {
+                    quote! { self.#ident().ok() }
+                }
+            } else {
+                quote! { self.#ident() }
+            };
+            quote! {
+                state.serialize_field(#getter, &#value)?;
+            }
+        })
+        .collect();
+    if items {
+        serialize_fields.push(quote! {
+            state.serialize_field("items", &self.items())?;
+        });
+    }
+    let name = ty.to_string().split_whitespace().next().unwrap().to_owned();
+    let len = serialize_fields.len();
+    let state = if len == 0 {
+        quote! { state }
+    } else {
+        quote! { mut state }
+    };
+    quote! {
+        #[cfg(feature = "serde")]
+        impl #generics serde::Serialize for #ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct;
+                let #state = serializer.serialize_struct(#name, #len)?;
+                #(#serialize_fields)*
+                state.end()
+            }
+        }
+    }
+}
+
+/// Raw setter and the type it takes, setters of variable length messages extend the payload
+/// up to the field. Fields located by `bits_fn` are left alone if that fails.
+fn codegen_raw_set_impl(
+    pgninfo: &PgnInfo,
+    field: &Field,
+    field_name: &Ident,
+    bits_fn: Option<&Ident>,
+) -> (TokenStream, TokenStream) {
+    let (rust_type_raw, is_slice) = field.raw_type();
+
+    let bit_offset = field.bit_offset;
+    let bit_length = field.bit_length;
+    let bit_end = bit_offset + bit_length;
+    let (range, extend) = match bits_fn {
+        Some(_) => (
+            quote! { range.clone() },
+            quote! {
+                self.len = self.len.max(range.end.div_ceil(8));
+            },
+        ),
+        None if pgninfo.is_variable_length() => {
+            let byte_end = bit_end.div_ceil(8);
+            (
+                quote! { #bit_offset .. #bit_end },
+                quote! {
+                    self.len = self.len.max(#byte_end);
+                },
+            )
+        }
+        None => (quote! { #bit_offset .. #bit_end }, quote! {}),
+    };
+
+    let bits = quote! {
+        self.raw.view_bits_mut::<Lsb0>()[#range]
+    };
+    let (value_type, set) = if !is_slice {
+        if field.is_signed() {
+            let signed_type = decode_signed_int_type_for_bit_length(field.bit_length);
+            (
+                signed_type,
+                quote! {
+                    let value = #rust_type_raw::from_ne_bytes(value.to_ne_bytes());
+                    #bits.store_le::<#rust_type_raw>(value);
+                    #extend
+                },
+            )
+        } else {
+            (
+                rust_type_raw.clone(),
+                quote! {
+                    #bits.store_le::<#rust_type_raw>(value);
+                    #extend
+                },
+            )
+        }
+    } else {
+        // Shorter values leave the remaining bits set
+        (
+            quote! { &[u8] },
+            quote! {
+                let bits = &mut #bits;
+                bits.set_all(true);
+                let length = core::cmp::min(value.len() * 8, #bit_length);
+                bits[..length].copy_from_bitslice(&value.view_bits::<Lsb0>()[..length]);
+                #extend
+            },
+        )
+    };
+    let set = match bits_fn {
+        Some(bits_fn) => {
+            let capacity_bits = pgninfo.capacity() * 8;
+            quote! {
+                if let Some(range) = self.#bits_fn().filter(|range| range.end <= #capacity_bits) {
+                    #set
+                }
+            }
+        }
+        None => set,
+    };
+    (
+        quote! {
+            pub fn #field_name(&mut self, value: #value_type) {
+                #set
+            }
+        },
+        value_type,
+    )
+}
+
+/// Setters of a variable length string, the raw one takes the encoded field. The encoding
+/// starts at bit `start`, an `Option<usize>`.
+fn codegen_string_set_impl(
+    pgninfo: &PgnInfo,
+    encoding: StringEncoding,
+    start: TokenStream,
+    field_name_raw: &Ident,
+    field_name: &Ident,
+) -> TokenStream {
+    let encoding = encoding.tokens();
+    let capacity = pgninfo.capacity();
+    quote! {
+        /// Replaces the encoded string, the fields after it move along
+        pub fn #field_name_raw(&mut self, value: &[u8]) {
+            if let Some(offset) = (#start).map(|start| start / 8).filter(|offset| *offset < #capacity) {
+                let old = self
+                    .as_bytes()
+                    .get(offset..)
+                    .and_then(|data| #encoding.field_len(data))
+                    .unwrap_or(0);
+                replace_field(&mut self.raw, &mut self.len, offset, old, value);
+            }
+        }
+
+        pub fn #field_name(&mut self, value: &str) {
+            let mut buf = [0u8; 255];
+            let len = #encoding.encode(value, &mut buf);
+            self.#field_name_raw(&buf[..len])
+        }
+    }
+}
+
+/// Typed setter matching `codegen_get_impl`, the type it takes and the type that deserializes
+/// to it
+fn codegen_set_impl(
+    field: &Field,
+    field_name_raw: &Ident,
+    field_name: &Ident,
+) -> Option<(TokenStream, TokenStream, TokenStream)> {
+    let (rust_type_raw, is_slice) = field.raw_type();
+    let rust_type = field.to_rust_type()?;
+
+    // type taken and the expression converting `value` to the raw value
+    let (value_type, raw) = if field.is_string() {
+        // string, padded with 0xff
+        (quote! { &str }, quote! { value.as_bytes() })
+    } else if field.is_date_time() && !is_slice {
+        let getter = if field.n2k_type == "Date" {
+            quote! { days }
+        } else {
+            quote! { ticks }
+        };
+        // date or time, out of range values are truncated
+        (rust_type, quote! { value.#getter() as #rust_type_raw })
+    } else if field.is_enum() || field.is_bitfield() {
+        // lookup table or flags
+        (rust_type, quote! { value.into() })
+    } else if field.is_float() && !is_slice {
+        let resolution = TokenStream::from_str(&field.resolution.to_string()).unwrap();
+        let raw_type = if field.is_signed() {
+            decode_signed_int_type_for_bit_length(field.bit_length)
+        } else {
+            rust_type_raw
+        };
+        let round = if rust_type.to_string() == "f64" {
+            quote! { round_f64 }
+        } else {
+            quote! { round }
+        };
+        let mut raw = quote! { (#round(value / (#resolution as #rust_type)) as i128) };
+        if field.offset != 0 {
+            let offset = Literal::i128_unsuffixed(field.offset as i128);
+            raw = quote! { (#raw - #offset) };
+        }
+        let [min, max] = field.value_range().map(Literal::i128_unsuffixed);
+        // float, out of range values are clamped to the values the field holds
+        (rust_type, quote! { #raw.clamp(#min, #max) as #raw_type })
+    } else if field.offset != 0 && !is_slice && field.bit_length <= 32 {
+        let offset = Literal::i128_unsuffixed(field.offset as i128);
+        let [min, max] = field.value_range().map(Literal::i128_unsuffixed);
+        // integer with an offset, out of range values are clamped to the values the field holds
+        (
+            quote! { i64 },
+            quote! { (value as i128 - #offset).clamp(#min, #max) as #rust_type_raw },
+        )
+    } else if field.is_plain_integer() {
+        // integer, written as is
+        (field.int_type(), quote! { value })
+    } else {
+        return None;
+    };
+
+    // Reserved raw values can be written as well
+    let (value_type, de_type, raw) = if field.has_reserved_values() {
+        let [not_available, out_of_range, reserved] =
+            field.reserved_values().map(Literal::i128_unsuffixed);
+        (
+            quote! { impl Into<N2kValue<#value_type>> },
+            quote! { N2kValue<#value_type> },
+            quote! {
+                match value.into() {
+                    N2kValue::Value(value) => #raw,
+                    N2kValue::NotAvailable => #not_available,
+                    N2kValue::OutOfRange => #out_of_range,
+                    N2kValue::Reserved => #reserved,
+                }
+            },
+        )
+    } else {
+        (value_type.clone(), value_type, raw)
+    };
+    Some((
+        quote! {
+            pub fn #field_name(&mut self, value: #value_type) {
+                self.#field_name_raw(#raw)
+            }
+        },
+        value_type,
+        de_type,
+    ))
+}
+
+/// Encodings of variable length strings, `StringEncoding` of the generated code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StringEncoding {
+    Lau,
+    Lz,
+    StartStop,
+}
+
+impl StringEncoding {
+    fn tokens(self) -> TokenStream {
+        match self {
+            StringEncoding::Lau => quote! { StringEncoding::Lau },
+            StringEncoding::Lz => quote! { StringEncoding::Lz },
+            StringEncoding::StartStop => quote! { StringEncoding::StartStop },
+        }
+    }
+
+    /// Bytes taken by an empty string
+    fn empty_len(self) -> usize {
+        match self {
+            StringEncoding::Lau => 2,
+            StringEncoding::Lz => 1,
+            StringEncoding::StartStop => 2,
+        }
+    }
+}
+
+/// Where a field starts in the payload
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldPosition {
+    /// Bit offset
+    Fixed(usize),
+    /// Bits after the end of the variable length string at this index of the `fixed_fields`
+    After(usize, usize),
+}
+
+impl PgnInfo {
+    /// Whether payloads may be shorter or longer than `length`, which is the case for
+    /// everything sent in more than a single frame. canboat lays out fields of some single
+    /// frame PGNs past their length, which then may not have been received either.
+    pub fn is_variable_length(&self) -> bool {
+        self.xtype != "Single" || self.bit_extent().div_ceil(8) > self.length
+    }
+
+    /// Bytes kept of variable length payloads
+    pub fn capacity(&self) -> usize {
+        self.length
+            .max(MAX_FAST_PACKET_SIZE)
+            .max(self.bit_extent().div_ceil(8))
+    }
+
+    /// End of the last field as laid out by canboat
+    fn bit_extent(&self) -> usize {
+        self.fields
+            .fields
+            .iter()
+            .map(|field| field.bit_offset + field.bit_length)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Bytes up to the end of the last field before any repeating group, with every variable
+    /// length string empty
+    pub fn fixed_length(&self) -> usize {
+        let bits = self.empty_layout().into_iter().map(|bits| bits.end).max();
+        bits.map_or(self.length, |bits| bits.div_ceil(8))
+    }
+
+    /// Whether fields move with the length of the variable length strings before them
+    pub fn has_variable_fields(&self) -> bool {
+        self.is_variable_length()
+            && self
+                .fixed_fields()
+                .iter()
+                .any(|field| field.string_encoding().is_some())
+    }
+
+    /// Variable length string encoding of `field`, None if its length is fixed in this PGN
+    fn variable_encoding(&self, field: &Field) -> Option<StringEncoding> {
+        field
+            .string_encoding()
+            .filter(|_| self.is_variable_length())
+    }
+
+    /// Positions of the `fixed_fields`, canboat gives no offset for fields after a variable
+    /// length string so they are placed by the bit lengths of the fields in between
+    fn field_positions(&self) -> Vec<FieldPosition> {
+        let mut positions = vec![];
+        let mut after = None;
+        for (index, field) in self.fixed_fields().iter().enumerate() {
+            positions.push(match after {
+                Some((string, bits)) => FieldPosition::After(string, bits),
+                None => FieldPosition::Fixed(field.bit_offset),
+            });
+            after = match (self.variable_encoding(field), after) {
+                (Some(_), _) => Some((index, 0)),
+                (None, Some((string, bits))) => Some((string, bits + field.bit_length)),
+                (None, None) => None,
+            };
+        }
+        positions
+    }
+
+    /// Bit ranges of the `fixed_fields` with every variable length string empty
+    fn empty_layout(&self) -> Vec<Range<usize>> {
+        let mut layout: Vec<Range<usize>> = vec![];
+        for (field, position) in self.fixed_fields().iter().zip(self.field_positions()) {
+            let start = match position {
+                FieldPosition::Fixed(bit_offset) => bit_offset,
+                FieldPosition::After(string, bits) => layout[string].end + bits,
+            };
+            let bit_length = match self.variable_encoding(field) {
+                Some(encoding) => encoding.empty_len() * 8,
+                None => field.bit_length,
+            };
+            layout.push(start..start + bit_length);
+        }
+        layout
+    }
+
+    /// Name canboat gives the PGN, its id if there is none
+    pub fn name(&self) -> &str {
+        if self.description.is_empty() {
+            &self.id
+        } else {
+            &self.description
+        }
+    }
+
+    /// Priority the PGN is sent with unless there is a reason to change it, canboat's or the
+    /// default of 6
+    pub fn priority(&self) -> u8 {
+        self.priority.filter(|priority| *priority <= 7).unwrap_or(6)
+    }
+
+    /// Whether the PGN has variants told apart by field values
+    pub fn has_match_fields(&self) -> bool {
+        self.fields.fields.iter().any(|f| f.match_value.is_some())
+    }
+
+    /// Fields repeated after the fixed ones, empty if the layout of the group is unknown
+    pub fn repeating_group(&self) -> &[Field] {
+        let fields = &self.fields.fields;
+        if self.repeating_fields == 0
+            || self.repeating_fields > fields.len()
+            || self.repeating_bit_length().is_none()
+        {
+            return &[];
+        }
+        &fields[fields.len() - self.repeating_fields..]
+    }
+
+    /// Fields before the repeating group, every field if there is none
+    pub fn fixed_fields(&self) -> &[Field] {
+        let fields = &self.fields.fields;
+        &fields[..fields.len() - self.repeating_group().len()]
+    }
+
+    /// Bits taken by one repeating group, None if any field of it has no fixed position
+    pub fn repeating_bit_length(&self) -> Option<usize> {
+        let fields = &self.fields.fields;
+        let group = fields.get(fields.len().checked_sub(self.repeating_fields)?..)?;
+        let start = group.first()?.bit_offset;
+        let mut end = start;
+        for field in group {
+            if field.bit_length == 0 || field.bit_offset != end || field.string_encoding().is_some()
+            {
+                return None;
+            }
+            end += field.bit_length;
+        }
+        Some(end - start)
+    }
+
+    /// Field preceding the repeating group that holds the number of groups. canboat does not
+    /// mark it, so it is recognized by its name.
+    pub fn count_field(&self) -> Option<&Field> {
+        let fixed = self.fixed_fields();
+        if fixed.len() == self.fields.fields.len() {
+            return None;
+        }
+        fixed.last().filter(|field| field.is_count())
+    }
 }
 
 impl Field {
+    /// Factor of the raw value, the resolution without the error of the `f32` it is parsed into
+    pub fn scale(&self) -> f64 {
+        if self.resolution == 0.0 {
+            1.0
+        } else {
+            self.resolution.to_string().parse().unwrap()
+        }
+    }
+
+    /// Whether the name of the field says it holds the number of repeating groups
+    pub fn is_count(&self) -> bool {
+        let name = self.name.to_lowercase();
+        name.starts_with("number of")
+            || name.starts_with("# of")
+            || name.ends_with("count")
+            || name == "sats in view"
+            || name == "reference stations"
+    }
+
     pub fn is_float(&self) -> bool {
         (self.resolution - 1.0).abs() > f32::EPSILON && self.resolution != 0.0
     }
@@ -544,26 +2486,133 @@ impl Field {
         self.n2k_type == "ASCII text"
     }
 
+    /// Whether the raw value is signed. canboat marks the fields with an offset as signed, but
+    /// like in J1939 they are unsigned and the offset makes them negative.
+    pub fn is_signed(&self) -> bool {
+        self.signed && self.offset == 0
+    }
+
+    /// Unit of the value, None if there is none. canboat puts some descriptions there.
+    pub fn unit(&self) -> Option<&str> {
+        Some(self.units.as_str()).filter(|units| !units.is_empty() && !units.contains('='))
+    }
+
+    /// Whether the field is a "Date" or "Time", both are integers scaled by the types
+    pub fn is_date_time(&self) -> bool {
+        self.n2k_type == "Date" || self.n2k_type == "Time"
+    }
+
     pub fn is_enum(&self) -> bool {
         !self.enum_values.enum_values.is_empty()
     }
 
+    /// Whether the field is a bitfield with named bits that fits an integer
+    pub fn is_bitfield(&self) -> bool {
+        !self.enum_bit_values.enum_bit_values.is_empty() && (1..=64).contains(&self.bit_length)
+    }
+
+    /// Whether the decoded value is a number whose highest raw values NMEA 2000 reserves, its
+    /// getter returns an `N2kValue`
+    pub fn has_reserved_values(&self) -> bool {
+        let (_, is_slice) = self.raw_type();
+        !is_slice
+            && !self.is_enum()
+            && !self.is_bitfield()
+            && self.to_rust_type().is_some()
+            && (self.is_date_time()
+                || self.is_float()
+                || (self.offset != 0 && self.bit_length <= 32)
+                || self.is_plain_integer())
+    }
+
+    /// Whether the field is an integer without resolution, offset or lookup table, such as a
+    /// SID or an instance. Counts of repeating groups use every value, and fields of less than
+    /// 4 bits are too short to reserve three.
+    pub fn is_plain_integer(&self) -> bool {
+        matches!(self.n2k_type.as_str(), "" | "Integer")
+            && !self.is_float()
+            && self.offset == 0
+            && !self.is_enum()
+            && !self.is_bitfield()
+            && !self.is_count()
+            && (4..=64).contains(&self.bit_length)
+    }
+
+    /// Type of the integer read from the bits, signed fields are sign extended
+    pub fn int_type(&self) -> TokenStream {
+        if self.is_signed() {
+            decode_signed_int_type_for_bit_length(self.bit_length)
+        } else {
+            decode_unsigned_int_type_for_bit_length(self.bit_length).0
+        }
+    }
+
+    /// Raw values meaning not available, out of range and reserved, the highest three
+    pub fn reserved_values(&self) -> [i128; 3] {
+        let max = if self.is_signed() {
+            (1i128 << (self.bit_length - 1)) - 1
+        } else {
+            (1i128 << self.bit_length) - 1
+        };
+        [max, max - 1, max - 2]
+    }
+
+    /// Lowest and highest raw value of a number, the reserved values above it are left out
+    pub fn value_range(&self) -> [i128; 2] {
+        let min = if self.is_signed() {
+            -(1i128 << (self.bit_length - 1))
+        } else {
+            0
+        };
+        let [_, _, reserved] = self.reserved_values();
+        [min, reserved - 1]
+    }
+
+    /// Encoding of variable length strings
+    pub fn string_encoding(&self) -> Option<StringEncoding> {
+        match self.n2k_type.as_str() {
+            "ASCII or UNICODE string starting with length and control byte" => {
+                Some(StringEncoding::Lau)
+            }
+            "ASCII string starting with length byte" => Some(StringEncoding::Lz),
+            "String with start/stop byte" => Some(StringEncoding::StartStop),
+            _ => None,
+        }
+    }
+
+    /// Type of the raw getter and whether it is a byte slice, strings always are
+    pub fn raw_type(&self) -> (TokenStream, bool) {
+        if self.is_string() || self.string_encoding().is_some() {
+            (quote! { &'a [u8] }, true)
+        } else {
+            decode_unsigned_int_type_for_bit_length(self.bit_length)
+        }
+    }
+
     pub fn to_rust_type(&self) -> Option<TokenStream> {
+        // Offsets make unsigned integers negative
+        if self.offset != 0 && !self.is_float() && (1..=32).contains(&self.bit_length) {
+            return Some(quote! {i64});
+        }
         Some(match self.n2k_type.as_str() {
             "Binary data" => decode_unsigned_int_type_for_bit_length(self.bit_length).0,
-            "Lookup table" => lookup_table_type(&self),
+            "Lookup table" => lookup_table_type(self),
             "Manufacturer code" => quote! {u16},
             "ASCII text" => quote! {&'a str},
-            "Date" => return None,
-            "Time" => return None,
-            "ASCII or UNICODE string starting with length and control byte" => return None,
-            "ASCII string starting with length byte" => return None,
-            "String with start/stop byte" => return None,
+            "Date" => quote! {N2kDate},
+            "Time" => quote! {N2kTime},
+            "ASCII or UNICODE string starting with length and control byte"
+            | "ASCII string starting with length byte"
+            | "String with start/stop byte" => quote! {N2kString<'a>},
+            "Bitfield" if self.is_bitfield() => lookup_table_type(self),
             "Bitfield" => return None,
+            // f32 is off by up to a metre at the resolution of 1e-7 degrees
+            "Latitude" | "Longitude" if (1..=64).contains(&self.bit_length) => quote! {f64},
             "Latitude"
             | "IEEE Float"
             | "Longitude"
             | "Temperature"
+            | "Pressure"
             | "Pressure (hires)"
             | "Temperature (hires)" => decode_float_type_for_bit_length(self.bit_length),
             "Decimal encoded number" => decode_unsigned_int_type_for_bit_length(self.bit_length).0,
@@ -576,12 +2625,10 @@ impl Field {
                 }
             }
             "Integer" => {
-                if !self.is_float() {
-                    decode_unsigned_int_type_for_bit_length(self.bit_length).0
+                if self.is_float() {
+                    decode_float_type_for_bit_length(self.bit_length)
                 } else {
-                    eprintln!("resolution = {:#?}", self.resolution);
-                    eprintln!("bit_length = {:#?}", self.bit_length);
-                    unimplemented!()
+                    decode_unsigned_int_type_for_bit_length(self.bit_length).0
                 }
             }
             x => panic!("unhandled N2K type {}", x),
@@ -590,7 +2637,7 @@ impl Field {
 }
 
 fn lookup_table_type(field: &Field) -> TokenStream {
-    let name = format_ident!("{}", field.id.to_camel_case());
+    let name = format_ident!("{}", type_name(&field.id));
     quote! { #name }
 }
 
@@ -611,6 +2658,11 @@ fn decode_unsigned_int_type_for_bit_length(bit_length: usize) -> (TokenStream, b
     }
 }
 
+/// Bits of the integer types holding a field of `bit_length`
+fn int_bit_length(bit_length: usize) -> usize {
+    bit_length.next_power_of_two().max(8)
+}
+
 fn decode_signed_int_type_for_bit_length(bit_length: usize) -> TokenStream {
     match bit_length {
         _a if _a > 32 && _a <= 64 => quote! { i64 },
@@ -623,6 +2675,7 @@ fn decode_signed_int_type_for_bit_length(bit_length: usize) -> TokenStream {
 
 fn decode_float_type_for_bit_length(bit_length: usize) -> TokenStream {
     match bit_length {
+        _a if _a > 32 && _a <= 64 => quote! { f64 },
         _a if _a > 16 && _a < 33 => quote! { f32 },
         _a if (8..17).contains(&_a) => quote! { f32 },
         _a if _a < 8 => quote! { f32 },
@@ -638,6 +2691,15 @@ fn type_name(x: &str) -> String {
     }
 }
 
+fn const_name(x: &str) -> String {
+    let name = x.to_shouty_snake_case();
+    if keywords::is_keyword(&name) || !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        format!("X_{}", name)
+    } else {
+        name
+    }
+}
+
 fn field_name(x: &str) -> String {
     if keywords::is_keyword(x) || !x.starts_with(|c: char| c.is_ascii_alphabetic()) {
         format!("x{}", x.to_snake_case())
@@ -654,4 +2716,428 @@ mod tests {
     fn it_works() {
         env_logger::init();
     }
+
+    fn pgns_file() -> PgnsFile {
+        let xml =
+            std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/pgns.xml")).unwrap();
+        let mut content: PgnsFile = serde_xml_rs::from_str(&xml).unwrap();
+        dedup_ids(&mut content);
+        content
+    }
+
+    fn pgn_info(pgn: u32) -> PgnInfo {
+        pgns_file()
+            .pgns
+            .pgn_infos
+            .into_iter()
+            .find(|info| info.pgn == pgn)
+            .unwrap()
+    }
+
+    #[test]
+    fn setters_names() {
+        // 130316 Temperature Extended Range has both "temperature" and "setTemperature"
+        let setters = codegen_setters(&pgn_info(130316), &HashMap::new())
+            .0
+            .to_string();
+        assert!(setters.contains("fn set_temperature_value_raw"));
+        assert!(setters.contains("fn set_temperature_value ("));
+        assert!(setters.contains("fn set_set_temperature ("));
+        assert!(!setters.contains("fn set_temperature ("));
+    }
+
+    #[test]
+    fn variant_enum_matches() {
+        let content = pgns_file();
+        let pgns = [130820, 127250].iter().cloned().collect();
+        let variants = codegen_pgns_variant_enum(&content, &pgns).to_string();
+        // 65325 comes first with the same id
+        assert!(variants.contains("if crate :: SimnetReprogramStatus1 :: matches (bytes)"));
+        assert!(variants.contains("Pgn :: Raw (RawPgn :: new (pgn , bytes))"));
+        assert!(variants.contains(
+            "127250u32 => Pgn :: VesselHeading (crate :: VesselHeading :: try_from (bytes) ?)"
+        ));
+
+        assert!(
+            variants.contains("Pgn :: VesselHeading (_) => crate :: VesselHeading :: PRIORITY ,")
+        );
+        assert!(variants.contains("Pgn :: Raw (_) => n2k :: Priority :: Priority6 ,"));
+        assert!(variants.contains(
+            "pub fn to_bytes (& self , buf : & mut [u8]) -> Result < usize , N2kError >"
+        ));
+
+        let tokens = codegen_impl(&pgn_info(127250)).to_string();
+        assert!(tokens.contains("pub const NAME : & 'static str = \"Vessel Heading\" ;"));
+        assert!(tokens
+            .contains("pub const PRIORITY : n2k :: Priority = n2k :: Priority :: Priority6 ;"));
+
+        let (matches, presets) = codegen_match_fields(&pgn_info(130820));
+        assert!(matches
+            .to_string()
+            .contains("load_le :: < u16 > () == 1857"));
+        assert!(presets.to_string().contains("store_le :: < u8 > (4)"));
+    }
+
+    #[test]
+    fn repeating_groups() {
+        struct TestCase {
+            pgn: u32,
+            fixed_fields: usize,
+            bit_length: Option<usize>,
+            count_field: Option<&'static str>,
+        }
+        let test_cases = [
+            TestCase {
+                pgn: 129540,
+                fixed_fields: 4,
+                bit_length: Some(96),
+                count_field: Some("satsInView"),
+            },
+            TestCase {
+                pgn: 126464,
+                fixed_fields: 1,
+                bit_length: Some(24),
+                count_field: None,
+            },
+            TestCase {
+                pgn: 127250,
+                fixed_fields: 6,
+                bit_length: None,
+                count_field: None,
+            },
+        ];
+        for i in &test_cases {
+            let info = pgn_info(i.pgn);
+            assert_eq!(i.fixed_fields, info.fixed_fields().len());
+            assert_eq!(i.bit_length, info.repeating_bit_length());
+            assert_eq!(i.count_field, info.count_field().map(|f| f.id.as_str()));
+        }
+
+        let (items, item) = codegen_repeating(&pgn_info(129540)).unwrap();
+        assert!(items
+            .to_string()
+            .contains("self . sats_in_view_raw () . map_or (0 , | count | count as usize)"));
+        assert!(item
+            .to_string()
+            .contains("const BIT_LENGTH : usize = 96usize"));
+        // prn is the first field of the group
+        assert!(item
+            .to_string()
+            .contains("self . raw . view_bits :: < Lsb0 > () [self . offset + 0usize .. self . offset + 8usize]"));
+    }
+
+    #[test]
+    fn variable_length() {
+        let product = pgn_info(126996);
+        assert!(product.is_variable_length());
+        assert_eq!(MAX_FAST_PACKET_SIZE, product.capacity());
+        assert_eq!(134, product.fixed_length());
+        let getters = codegen_getters(&product, product.fixed_fields(), None, &HashMap::new())
+            .0
+            .to_string();
+        assert!(getters.contains("pub fn model_id < 'a > (& 'a self) -> Option < Result"));
+        assert!(getters.contains("[.. self . len * 8] . get (32usize .. 288usize) ?"));
+
+        // 129540 is announced at 233 bytes, built without any satellite
+        assert_eq!(3, pgn_info(129540).fixed_length());
+
+        let heading = pgn_info(127250);
+        assert!(!heading.is_variable_length());
+        let getters = codegen_getters(&heading, heading.fixed_fields(), None, &HashMap::new())
+            .0
+            .to_string();
+        assert!(getters.contains("pub fn heading (& self) -> N2kValue < f32 >"));
+    }
+
+    #[test]
+    fn offsets_units() {
+        // 65013 Utility Phase A AC Power is marked signed, the offset makes it negative
+        let power = pgn_info(65013);
+        let getters = codegen_getters(&power, power.fixed_fields(), None, &HashMap::new())
+            .0
+            .to_string();
+        assert!(getters.contains("pub fn real_power_raw (& self) -> u32"));
+        assert!(getters.contains("pub const REAL_POWER_UNIT : & 'static str = \"W\" ;"));
+        assert!(getters.contains(
+            "# [doc = \"Real Power in W\"] pub fn real_power (& self) -> N2kValue < i64 > { let raw = self . real_power_raw () ; \
+             match raw { 4294967295 => N2kValue :: NotAvailable , 4294967294 => N2kValue :: OutOfRange , \
+             4294967293 => N2kValue :: Reserved , raw => N2kValue :: Value (raw as i64 + - 2000000000i64) , } }"
+        ));
+        assert!(getters.contains(
+            "# [cfg (feature = \"uom\")] pub fn real_power_quantity (& self) -> N2kValue < uom :: si :: f32 :: Power >"
+        ));
+        let setters = codegen_setters(&power, &HashMap::new()).0.to_string();
+        assert!(setters.contains(
+            "pub fn set_real_power (& mut self , value : impl Into < N2kValue < i64 >>) { self . set_real_power_raw (match value . into () { \
+             N2kValue :: Value (value) => (value as i128 - - 2000000000) . clamp (0 , 4294967292) as u32 , \
+             N2kValue :: NotAvailable => 4294967295 ,"
+        ));
+
+        let temperature = pgn_info(130316);
+        let getters = codegen_getters(
+            &temperature,
+            temperature.fixed_fields(),
+            None,
+            &HashMap::new(),
+        )
+        .0
+        .to_string();
+        assert!(getters.contains("pub const TEMPERATURE_UNIT : & 'static str = \"K\" ;"));
+        assert!(getters.contains(
+            "ThermodynamicTemperature :: new :: < uom :: si :: thermodynamic_temperature :: kelvin > (value as f32)"
+        ));
+    }
+
+    #[test]
+    fn bitfields() {
+        let engine = pgn_info(127489);
+        let status = engine
+            .fields
+            .fields
+            .iter()
+            .find(|field| field.id == "discreteStatus1")
+            .unwrap();
+        assert!(status.is_bitfield());
+        let flags = codegen_bitfield(status).to_string();
+        assert!(flags.contains("pub struct DiscreteStatus1 (u16) ;"));
+        assert!(flags.contains(
+            "# [doc = \"Low Oil Pressure\"] pub const LOW_OIL_PRESSURE : Self = Self (1 << 2usize) ;"
+        ));
+        assert!(flags.contains("(\"Check Engine\" , Self :: CHECK_ENGINE)"));
+
+        let getters = codegen_getters(&engine, engine.fixed_fields(), None, &HashMap::new())
+            .0
+            .to_string();
+        assert!(getters.contains(
+            "pub fn discrete_status1 (& self) -> Option < DiscreteStatus1 > { self . discrete_status1_raw () . map (| raw | raw . into ()) }"
+        ));
+    }
+
+    #[test]
+    fn date_time() {
+        let system_time = pgn_info(126992);
+        let getters = codegen_getters(
+            &system_time,
+            system_time.fixed_fields(),
+            None,
+            &HashMap::new(),
+        )
+        .0
+        .to_string();
+        assert!(getters.contains("pub fn date (& self) -> N2kValue < N2kDate > { let raw = self . date_raw () ; match raw { 65535 => N2kValue :: NotAvailable"));
+        assert!(
+            getters.contains("raw => N2kValue :: Value (N2kDate :: from_days (raw . into ())) ,")
+        );
+        assert!(
+            getters.contains("raw => N2kValue :: Value (N2kTime :: from_ticks (raw . into ())) ,")
+        );
+
+        let setters = codegen_setters(&system_time, &HashMap::new()).0.to_string();
+        assert!(setters.contains("N2kValue :: Value (value) => value . days () as u16 ,"));
+        assert!(setters.contains("N2kValue :: Value (value) => value . ticks () as u32 ,"));
+        assert!(setters.contains("N2kValue :: Reserved => 4294967293 ,"));
+    }
+
+    #[test]
+    fn variable_strings() {
+        // Built with an empty name and every other field after it
+        let library = pgn_info(130570);
+        assert!(library.has_variable_fields());
+        assert_eq!(27, library.fixed_length());
+
+        let (bits_fns, names) = codegen_bits_fns(&library);
+        let bits_fns = bits_fns.to_string();
+        assert!(bits_fns.contains(
+            "fn name_bits (& self) -> Option < core :: ops :: Range < usize >> { let start = 56usize ; \
+             Some (start .. start + StringEncoding :: Lau . field_len"
+        ));
+        assert!(bits_fns.contains("let start = self . name_bits () ? . end + 16usize ;"));
+
+        let getters = codegen_getters(&library, library.fixed_fields(), None, &names)
+            .0
+            .to_string();
+        assert!(getters.contains("pub fn name < 'a > (& 'a self) -> Option < N2kString < 'a > >"));
+        assert!(getters.contains("get (self . track_bits () ?) ?"));
+
+        let setters = codegen_setters(&library, &names).0.to_string();
+        assert!(setters.contains(
+            "replace_field (& mut self . raw , & mut self . len , offset , old , value)"
+        ));
+        assert!(setters.contains("if let Some (range) = self . track_bits ()"));
+        assert!(codegen_presets(&library).to_string().contains(
+            "raw [7usize .. 9usize] . copy_from_slice (StringEncoding :: Lau . empty ())"
+        ));
+    }
+
+    #[test]
+    fn signed_integers() {
+        let dc = pgn_info(127751);
+        let current = dc
+            .fields
+            .fields
+            .iter()
+            .find(|field| field.id == "dcCurrent")
+            .unwrap();
+        assert!(current.is_signed());
+        assert_eq!([8388607, 8388606, 8388605], current.reserved_values());
+        let getters = codegen_getters(&dc, dc.fixed_fields(), None, &HashMap::new())
+            .0
+            .to_string();
+        assert!(getters.contains(
+            "pub fn dc_current_raw (& self) -> i32 { let bits = & self . raw . view_bits :: < Lsb0 > () [32usize .. 56usize] ; \
+             ((bits . load_le :: < u32 > () << 8usize) as i32) >> 8usize }"
+        ));
+
+        // Laid out past its length, so it may not have been received
+        let altitude = pgn_info(129028);
+        assert!(altitude.is_variable_length());
+        assert_eq!(altitude.capacity(), MAX_FAST_PACKET_SIZE);
+    }
+
+    #[test]
+    fn plain_integers() {
+        let sats = pgn_info(129540);
+        let field = |id: &str| {
+            sats.fields
+                .fields
+                .iter()
+                .find(|field| field.id == id)
+                .unwrap()
+        };
+        let residuals = field("rangeResiduals");
+        assert!(residuals.has_reserved_values());
+        assert_eq!(
+            [2147483647, 2147483646, 2147483645],
+            residuals.reserved_values()
+        );
+        assert!(!field("satsInView").has_reserved_values());
+        assert!(!field("mode").has_reserved_values());
+
+        let getters = codegen_getters(&sats, sats.fixed_fields(), None, &HashMap::new())
+            .0
+            .to_string();
+        assert!(getters.contains("pub fn sid_raw (& self) -> Option < u8 >"));
+        assert!(getters.contains(
+            "pub fn sid (& self) -> Option < N2kValue < u8 > > { self . sid_raw () . map (| raw | match raw { \
+             255 => N2kValue :: NotAvailable , 254 => N2kValue :: OutOfRange , 253 => N2kValue :: Reserved , \
+             raw => N2kValue :: Value (raw) , }) }"
+        ));
+        assert!(getters.contains("pub fn sats_in_view_raw (& self) -> Option < u8 >"));
+        assert!(!getters.contains("pub fn sats_in_view ("));
+        let setters = codegen_setters(&sats, &HashMap::new()).0.to_string();
+        assert!(setters.contains(
+            "pub fn set_sid (& mut self , value : impl Into < N2kValue < u8 >>) { self . set_sid_raw (match value . into () { \
+             N2kValue :: Value (value) => value , N2kValue :: NotAvailable => 255 ,"
+        ));
+    }
+
+    #[test]
+    fn known_vector_tests() {
+        let heading = pgn_info(127250);
+        let fields = heading.fixed_fields();
+        // Highest value short of the reserved ones, lowest value of signed fields
+        assert_eq!(known_raw_value(&fields[0], 0), 252);
+        assert_eq!(known_raw_value(&fields[2], 2), -32766);
+        // Alternating bits of the lookup table
+        assert_eq!(known_raw_value(&fields[4], 4), 1);
+
+        let test = codegen_known_vector_test(&heading).to_string();
+        assert!(test.contains("message . set_deviation (- 3.2766f64 as f32) ;"));
+        assert!(test.contains("assert_eq ! (decoded . deviation_raw () , - 32766) ;"));
+        assert!(test.contains("message . set_reference (Reference :: from (1u8)) ;"));
+        assert!(test.contains(
+            "assert_eq ! (decoded . heading_raw () , 65535) ; \
+             assert_eq ! (decoded . heading () , N2kValue :: NotAvailable) ;"
+        ));
+
+        // Latitudes of f64 stay within its precision
+        let gnss = pgn_info(129029);
+        let latitude = gnss.fixed_fields().iter().position(|f| f.id == "latitude");
+        let latitude = latitude.unwrap();
+        assert_eq!(
+            known_raw_value(&gnss.fixed_fields()[latitude], 0),
+            -(1 << 48)
+        );
+        let test = codegen_known_vector_test(&gnss).to_string();
+        assert!(test.contains("assert_eq ! (decoded . sid () , Some (N2kValue :: Value (252))) ;"));
+
+        // Only strings, left to the known vectors of the tests
+        assert!(codegen_known_vector_test(&pgn_info(126998)).is_empty());
+    }
+
+    #[test]
+    fn serde() {
+        let depth = pgn_info(128267);
+        let tokens = codegen_impl(&depth).to_string();
+        assert!(tokens.contains("state . serialize_field (\"depth\" , & self . depth ()) ? ;"));
+        assert!(tokens.contains(
+            "\"depth\" => { let value = map . next_value :: < N2kValue < f32 > > () ? ; builder . depth (value) }"
+        ));
+
+        let engine = pgn_info(127489);
+        let instance = &engine.fields.fields[0];
+        let lookup = codegen_enum(instance, &instance.enum_values).to_string();
+        assert!(lookup.contains("Self :: DualEngineStarboard => \"DualEngineStarboard\""));
+        assert!(lookup.contains("# [cfg (feature = \"serde\")] serde_lookup ! (Instance , u8) ;"));
+
+        // Invalid UTF-8 serializes as none
+        let product = pgn_info(126996);
+        let tokens = codegen_impl(&product).to_string();
+        assert!(tokens.contains("& self . model_id () . and_then (Result :: ok)"));
+        assert!(tokens.contains("builder . model_id (value . as_str ())"));
+    }
+
+    #[test]
+    fn positions() {
+        let gnss = pgn_info(129029);
+        let getters = codegen_getters(&gnss, gnss.fixed_fields(), None, &HashMap::new())
+            .0
+            .to_string();
+        assert!(getters.contains("pub fn latitude_raw (& self) -> Option < i64 >"));
+        assert!(getters.contains("pub fn latitude (& self) -> Option < N2kValue < f64 > >"));
+        assert!(getters
+            .contains("raw => N2kValue :: Value ((raw as f64) * (0.0000000000000001 as f64)) ,"));
+        let setters = codegen_setters(&gnss, &HashMap::new()).0.to_string();
+        assert!(setters.contains(
+            "N2kValue :: Value (value) => (round_f64 (value / (0.0000000000000001 as f64)) as i128) \
+             . clamp (- 9223372036854775808 , 9223372036854775804) as i64 ,"
+        ));
+        let (position, _) = codegen_position(&gnss).unwrap();
+        assert!(position
+            .to_string()
+            .contains("pub fn position (& self) -> Option < N2kValue < GeoPosition >>"));
+
+        // 32 bit fields are f64 as well
+        let rapid = pgn_info(129025);
+        let (position, builder) = codegen_position(&rapid).unwrap();
+        assert!(position.to_string().contains(
+            "self . latitude () . zip (self . longitude ()) . map (| (latitude , longitude) | GeoPosition :: new (latitude , longitude))"
+        ));
+        assert!(builder.to_string().contains(
+            "pub fn position (mut self , value : impl Into < N2kValue < GeoPosition >>) -> Self"
+        ));
+        assert!(codegen_position(&pgn_info(127250)).is_none());
+    }
+
+    #[test]
+    fn descriptors() {
+        let gnss = codegen_descriptor(&pgn_info(129029)).to_string();
+        assert!(gnss.contains("name : \"GNSS Position Data\" , priority : n2k :: Priority :: Priority6 , fast_packet : true , length : 51usize , repeating_fields : 3usize"));
+        assert!(gnss.contains("id : \"latitude\" , name : \"Latitude\" , kind : \"Latitude\" , bit_offset : Some (56usize) , bit_length : 64usize , signed : true , resolution : 0.0000000000000001 , offset : 0i64 , unit : Some (\"deg\")"));
+        assert!(gnss.contains("lookup : & [(0 , \"GPS\") , (1 , \"GLONASS\") ,"));
+
+        // Fields after a variable length string move with it
+        let configuration = codegen_descriptor(&pgn_info(126998)).to_string();
+        assert!(configuration.contains("id : \"installationDescription1\" , name : \"Installation Description #1\" , kind : \"ASCII or UNICODE string starting with length and control byte\" , bit_offset : Some (0usize)"));
+        assert!(configuration.contains("id : \"manufacturerInformation\" , name : \"Manufacturer Information\" , kind : \"ASCII or UNICODE string starting with length and control byte\" , bit_offset : None"));
+
+        let engine = codegen_descriptor(&pgn_info(127489)).to_string();
+        assert!(engine.contains("bits : & [(0 , \"Check Engine\") ,"));
+
+        let content = pgns_file();
+        let pgns = [130820, 127250].iter().cloned().collect();
+        let descriptors = codegen_pgns_descriptors(&content, &pgns).to_string();
+        assert!(descriptors.contains("crate :: SimnetReprogramStatus1 :: DESCRIPTOR"));
+        assert!(descriptors.contains("crate :: VesselHeading :: DESCRIPTOR"));
+    }
 }
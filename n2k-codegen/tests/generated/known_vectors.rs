@@ -0,0 +1,215 @@
+//! Known vectors of the generated crate. The payloads and expected values are worked out by hand
+//! from the field definitions in canboat's `pgns.xml`, not by the generator.
+
+use core::convert::TryFrom;
+use n2k_messages::messages::gnss_sats_in_view::Status;
+use n2k_messages::messages::vessel_heading::Reference;
+use n2k_messages::*;
+
+fn assert_close<T: Into<f64>>(value: N2kValue<T>, expected: f64) {
+    let value = value.value().expect("value").into();
+    assert!(
+        (value - expected).abs() <= expected.abs() * 1e-6 + 1e-9,
+        "{} != {}",
+        value,
+        expected
+    );
+}
+
+#[test]
+fn vessel_heading() {
+    // SID 18, heading 1.5708 rad, deviation -0.005 rad, variation not available, magnetic
+    let payload = [0x12, 0x5c, 0x3d, 0xce, 0xff, 0xff, 0x7f, 0xfd];
+    let message = VesselHeading::try_from(&payload[..]).unwrap();
+    assert_eq!(message.sid(), N2kValue::Value(18));
+    assert_eq!(message.heading_raw(), 15708);
+    assert_close(message.heading(), 1.5708);
+    assert_eq!(message.deviation_raw(), -50);
+    assert_close(message.deviation(), -0.005);
+    assert_eq!(message.variation(), N2kValue::NotAvailable);
+    assert_eq!(message.reference(), Reference::Magnetic);
+
+    let built = VesselHeading::builder()
+        .sid(18)
+        .heading(1.5708)
+        .deviation(-0.005)
+        .reference(Reference::Magnetic)
+        .build();
+    assert_eq!(built.as_bytes(), &payload[..]);
+}
+
+#[test]
+fn water_depth() {
+    // SID not available, depth 10.5 m, offset -0.5 m, range out of range
+    let payload = [0xff, 0x1a, 0x04, 0x00, 0x00, 0x0c, 0xfe, 0xfe];
+    let message = match Pgn::try_from_bytes(128267, &payload).unwrap() {
+        Pgn::WaterDepth(message) => message,
+        pgn => panic!("{:?}", pgn),
+    };
+    assert_eq!(message.sid(), N2kValue::NotAvailable);
+    assert_eq!(message.depth_raw(), 1050);
+    assert_close(message.depth(), 10.5);
+    assert_eq!(message.offset_raw(), -500);
+    assert_close(message.offset(), -0.5);
+    assert_eq!(message.range(), N2kValue::OutOfRange);
+}
+
+#[test]
+fn position_rapid_update() {
+    // 52.3676 N 4.9041 W
+    let payload = [0x60, 0xa9, 0x36, 0x1f, 0x98, 0xb1, 0x13, 0xfd];
+    let message = PositionRapidUpdate::try_from(&payload[..]).unwrap();
+    assert_eq!(message.latitude_raw(), 523_676_000);
+    assert_close(message.latitude(), 52.3676);
+    assert_eq!(message.longitude_raw(), -49_041_000);
+    assert_close(message.longitude(), -4.9041);
+
+    let built = PositionRapidUpdate::builder()
+        .latitude(52.3676)
+        .longitude(-4.9041)
+        .build();
+    assert_eq!(built.as_bytes(), &payload[..]);
+}
+
+#[test]
+fn system_time() {
+    // SID 1, GPS, 2021-03-04 12:34:56.789
+    let payload = [0x01, 0xf0, 0x02, 0x49, 0xd2, 0xbd, 0xff, 0x1a];
+    let message = SystemTime::try_from(&payload[..]).unwrap();
+    assert_eq!(message.date_raw(), 18690);
+    let date = message.date().value().unwrap();
+    assert_eq!(date.ymd(), (2021, 3, 4));
+    assert_eq!(message.time_raw(), 452_967_890);
+    let time = message.time().value().unwrap();
+    assert_eq!(time.hms(), Some((12, 34, 56)));
+    assert_eq!(time.subsec_nanos(), 789_000_000);
+
+    let built = SystemTime::builder()
+        .sid(1)
+        .source(n2k_messages::messages::system_time::Source::Gps)
+        .date(N2kDate::from_ymd(2021, 3, 4).unwrap())
+        .time(N2kTime::from_hms_nano(12, 34, 56, 789_000_000).unwrap())
+        .build();
+    assert_eq!(built.as_bytes(), &payload[..]);
+}
+
+#[test]
+fn dc_voltage_current() {
+    // SID 0, connection 2, 12.6 V, -12.34 A in 24 bits
+    let payload = [0x00, 0x02, 0x7e, 0x00, 0x2e, 0xfb, 0xff, 0xff];
+    let message = DcVoltageCurrent::try_from(&payload[..]).unwrap();
+    assert_eq!(message.connection_number(), N2kValue::Value(2));
+    assert_close(message.dc_voltage(), 12.6);
+    assert_eq!(message.dc_current_raw(), -1234);
+    assert_close(message.dc_current(), -12.34);
+
+    // The largest 24 bit value is not available
+    let payload = [0x00, 0x02, 0x7e, 0x00, 0xff, 0xff, 0x7f, 0xff];
+    let message = DcVoltageCurrent::try_from(&payload[..]).unwrap();
+    assert_eq!(message.dc_current_raw(), 0x7f_ffff);
+    assert_eq!(message.dc_current(), N2kValue::NotAvailable);
+}
+
+#[test]
+fn utility_phase_a_ac_power() {
+    // 1500 W real and -250 VA apparent power, both offset by -2000000000
+    let payload = [0xdc, 0x99, 0x35, 0x77, 0x06, 0x93, 0x35, 0x77];
+    let message = UtilityPhaseAAcPower::try_from(&payload[..]).unwrap();
+    assert_eq!(message.real_power_raw(), 2_000_001_500);
+    assert_eq!(message.real_power(), N2kValue::Value(1500));
+    assert_eq!(message.apparent_power_raw(), 1_999_999_750);
+    assert_eq!(message.apparent_power(), N2kValue::Value(-250));
+
+    let built = UtilityPhaseAAcPower::builder()
+        .real_power(1500)
+        .apparent_power(-250)
+        .build();
+    assert_eq!(built.as_bytes(), &payload[..]);
+
+    // Out of range powers are clamped short of the reserved values
+    let built = UtilityPhaseAAcPower::builder()
+        .real_power(3_000_000_000)
+        .apparent_power(-2_100_000_000)
+        .build();
+    assert_eq!(built.real_power_raw(), 0xffff_fffc);
+    assert_eq!(built.real_power(), N2kValue::Value(2_294_967_292));
+    assert_eq!(built.apparent_power_raw(), 0);
+    assert_eq!(built.apparent_power(), N2kValue::Value(-2_000_000_000));
+}
+
+#[test]
+fn generator_phase_c_ac_power() {
+    // The 16 bit fields cannot hold realistic powers with the offset of -2000000000
+    let built = GeneratorPhaseCAcPower::builder()
+        .real_power(1500)
+        .apparent_power(i64::MIN)
+        .build();
+    assert_eq!(built.real_power_raw(), 0xfffc);
+    assert_eq!(built.real_power(), N2kValue::Value(65532 - 2_000_000_000));
+    assert_eq!(built.apparent_power_raw(), 0);
+}
+
+#[test]
+fn gnss_sats_in_view() {
+    // SID 7, range residuals used, two satellites
+    let payload = [
+        0x07, 0xff, 0x02, //
+        // PRN 5, elevation 0.5236 rad, azimuth 3.1416 rad, SNR 42.5 dB, no residuals, used
+        0x05, 0x74, 0x14, 0xb8, 0x7a, 0x9a, 0x10, 0xff, 0xff, 0xff, 0x7f, 0xf2, //
+        // PRN 12, elevation 0.1 rad, azimuth 1 rad, SNR 30 dB, residuals -5, tracked
+        0x0c, 0xe8, 0x03, 0x10, 0x27, 0xb8, 0x0b, 0xfb, 0xff, 0xff, 0xff, 0xf1,
+    ];
+    let message = GnssSatsInView::try_from(&payload[..]).unwrap();
+    assert_eq!(message.sid(), Some(N2kValue::Value(7)));
+    assert_eq!(message.sats_in_view_raw(), Some(2));
+
+    let sats: Vec<_> = message.items().collect();
+    assert_eq!(sats.len(), 2);
+    assert_eq!(sats[0].prn(), N2kValue::Value(5));
+    assert_close(sats[0].elevation(), 0.5236);
+    assert_close(sats[0].azimuth(), 3.1416);
+    assert_close(sats[0].snr(), 42.5);
+    assert_eq!(sats[0].range_residuals(), N2kValue::NotAvailable);
+    assert_eq!(sats[0].status(), Status::Used);
+    assert_eq!(sats[1].prn(), N2kValue::Value(12));
+    assert_close(sats[1].elevation(), 0.1);
+    assert_close(sats[1].azimuth(), 1.0);
+    assert_close(sats[1].snr(), 30.0);
+    assert_eq!(sats[1].range_residuals(), N2kValue::Value(-5));
+    assert_eq!(sats[1].status(), Status::Tracked);
+}
+
+#[test]
+fn configuration_information() {
+    // Installation descriptions "Cockpit" and "", manufacturer information "n2k"
+    let payload = [
+        0x09, 0x01, b'C', b'o', b'c', b'k', b'p', b'i', b't', //
+        0x02, 0x01, //
+        0x05, 0x01, b'n', b'2', b'k',
+    ];
+    let message = ConfigurationInformation::try_from(&payload[..]).unwrap();
+    let description = message.installation_description1().unwrap();
+    assert_eq!(description.as_str(), Some("Cockpit"));
+    let description = message.installation_description2().unwrap();
+    assert_eq!(description.as_str(), Some(""));
+    let information = message.manufacturer_information().unwrap();
+    assert_eq!(information.as_str(), Some("n2k"));
+
+    let built = ConfigurationInformation::builder()
+        .installation_description1("Cockpit")
+        .manufacturer_information("n2k")
+        .build();
+    assert_eq!(built.as_bytes(), &payload[..]);
+}
+
+#[test]
+fn temperature_extended_range() {
+    // SID 3, instance 0, sea temperature, 293.150 K in 24 bits, set to 295.1 K
+    let payload = [0x03, 0x00, 0x00, 0x1e, 0x79, 0x04, 0x87, 0x0b];
+    let message = TemperatureExtendedRange::try_from(&payload[..]).unwrap();
+    assert_eq!(message.sid(), N2kValue::Value(3));
+    assert_eq!(message.instance(), N2kValue::Value(0));
+    assert_eq!(message.temperature_raw(), 293_150);
+    assert_close(message.temperature(), 293.15);
+    assert_close(message.set_temperature(), 295.1);
+}
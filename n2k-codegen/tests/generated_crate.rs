@@ -0,0 +1,64 @@
+//! Generates a crate with every PGN of `pgns.xml` and runs its tests, including the known
+//! vectors of `generated/known_vectors.rs`
+#![cfg(unix)]
+
+use n2k_codegen::canboatxml::PgnsFile;
+use n2k_codegen::N2kCodeGenOpts;
+use std::path::Path;
+use std::process::Command;
+
+/// bitvec 0.22 depends on funty 1.2, which was yanked and only resolves from a lock file
+const LOCK_FILE: &str = r#"version = 3
+
+[[package]]
+name = "funty"
+version = "1.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+#[test]
+fn generated_crate() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("generated");
+    let output = dir.join("n2k-messages");
+    std::fs::remove_dir_all(output.join("src")).ok();
+    std::fs::create_dir_all(output.join("tests")).unwrap();
+
+    // The generated crate depends on n2k next to it
+    let n2k = dir.join("n2k");
+    if !n2k.exists() {
+        std::os::unix::fs::symlink(manifest_dir.join("../n2k"), n2k).unwrap();
+    }
+    let lock_file = output.join("Cargo.lock");
+    if !lock_file.exists() {
+        std::fs::write(lock_file, LOCK_FILE).unwrap();
+    }
+
+    let pgns_xml = manifest_dir.join("pgns.xml");
+    let xml = std::fs::read_to_string(&pgns_xml).unwrap();
+    let pgns_file: PgnsFile = serde_xml_rs::from_str(&xml).unwrap();
+    n2k_codegen::codegen(N2kCodeGenOpts {
+        pgns_xml: pgns_xml.to_str().unwrap().to_owned(),
+        pgns: pgns_file
+            .pgns
+            .pgn_infos
+            .iter()
+            .map(|info| info.pgn)
+            .collect(),
+        output: output.clone(),
+        generate_crate: Some("n2k-messages".to_owned()),
+    });
+    std::fs::copy(
+        manifest_dir.join("tests/generated/known_vectors.rs"),
+        output.join("tests/known_vectors.rs"),
+    )
+    .unwrap();
+
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+    let status = Command::new(cargo)
+        .args(["test", "--all-features"])
+        .current_dir(&output)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -0,0 +1,272 @@
+use crate::Name;
+
+pub(crate) const PGN_ISO_ADDRESS_CLAIM: u32 = 60928;
+pub(crate) const PGN_COMMANDED_ADDRESS: u32 = 65240;
+
+/// Source address of a device that could not claim an address
+pub const NULL_ADDRESS: u8 = 254;
+/// Highest address a device can claim
+pub const MAX_ADDRESS: u8 = 253;
+/// Lowest address an arbitrary address capable device moves to after losing its address, the
+/// ones below are for devices with fixed addresses
+pub const MIN_DYNAMIC_ADDRESS: u8 = 128;
+
+/// Time in ms a claim must go uncontested before the address may be used
+pub(crate) const CLAIM_TIMEOUT: u64 = 250;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AddressClaimState {
+    /// Claim sent, waiting for contending claims
+    Claiming,
+    /// Address is ours, messages may be sent
+    Claimed,
+    /// Every address is taken or we lost ours without being arbitrary address capable
+    CannotClaim,
+}
+
+/// Approves addresses commanded by a 65240 Commanded Address, see
+/// `Bus::with_commanded_address_hook`
+pub trait CommandedAddressHook {
+    /// Called with the current and the new address, returning false vetoes the change.
+    /// Otherwise the new address is claimed and can be persisted by the hook.
+    fn accept(&mut self, current: u8, commanded: u8) -> bool;
+}
+
+/// Accepts every commanded address
+impl CommandedAddressHook for () {
+    fn accept(&mut self, _current: u8, _commanded: u8) -> bool {
+        true
+    }
+}
+
+impl<F: FnMut(u8, u8) -> bool> CommandedAddressHook for F {
+    fn accept(&mut self, current: u8, commanded: u8) -> bool {
+        self(current, commanded)
+    }
+}
+
+/// Set of source addresses, one bit per address
+#[derive(Clone, Default)]
+pub(crate) struct AddressSet([u64; 4]);
+
+impl AddressSet {
+    pub fn insert(&mut self, address: u8) {
+        self.0[address as usize / 64] |= 1 << (address % 64);
+    }
+
+    pub fn contains(&self, address: u8) -> bool {
+        self.0[address as usize / 64] & (1 << (address % 64)) != 0
+    }
+}
+
+/// Address claim procedure of ISO 11783-5 for a single device
+pub(crate) struct AddressClaim {
+    name: Name,
+    address: u8,
+    state: AddressClaimState,
+    deadline: u64,
+    // addresses claimed by other devices
+    claimed: AddressSet,
+}
+
+impl AddressClaim {
+    pub fn new(name: Name, address: u8, now: u64) -> Self {
+        AddressClaim {
+            name,
+            address: address.min(MAX_ADDRESS),
+            state: AddressClaimState::Claiming,
+            deadline: now + CLAIM_TIMEOUT,
+            claimed: AddressSet::default(),
+        }
+    }
+
+    /// Source address to use, `NULL_ADDRESS` if we could not claim one
+    pub fn address(&self) -> u8 {
+        match self.state {
+            AddressClaimState::CannotClaim => NULL_ADDRESS,
+            _ => self.address,
+        }
+    }
+
+    pub fn state(&self) -> AddressClaimState {
+        self.state
+    }
+
+    /// Payload of our address claim, sent from `address()`
+    pub fn claim_data(&self) -> [u8; 8] {
+        self.name.to_bytes()
+    }
+
+    /// Handles the claim of another device, returns whether our claim has to be sent (again)
+    pub fn process_claim(&mut self, source: u8, name: Name, now: u64) -> bool {
+        if source > MAX_ADDRESS || name == self.name {
+            return false;
+        }
+        self.claimed.insert(source);
+        if source != self.address || self.state == AddressClaimState::CannotClaim {
+            return false;
+        }
+
+        // The lower NAME has priority and keeps the address
+        if self.name < name {
+            log::info!(
+                "defending address {} against NAME {:#x}",
+                source,
+                name.value()
+            );
+            return true;
+        }
+
+        match self.next_free_address() {
+            Some(address) if self.name.arbitrary_address_capable() => {
+                log::info!("lost address {}, claiming {}", self.address, address);
+                self.address = address;
+                self.state = AddressClaimState::Claiming;
+                self.deadline = now + CLAIM_TIMEOUT;
+            }
+            _ => {
+                log::error!("lost address {}, cannot claim another", self.address);
+                self.state = AddressClaimState::CannotClaim;
+            }
+        }
+        true
+    }
+
+    /// Moves to `address` as commanded by a 65240 Commanded Address, the new address has to
+    /// be claimed like any other
+    pub fn command(&mut self, address: u8, now: u64) {
+        log::info!("commanded from address {} to {}", self.address, address);
+        self.address = address;
+        self.state = AddressClaimState::Claiming;
+        self.deadline = now + CLAIM_TIMEOUT;
+    }
+
+    pub fn name(&self) -> Name {
+        self.name
+    }
+
+    /// Completes the claim once it went uncontested for long enough
+    pub fn poll(&mut self, now: u64) {
+        if self.state == AddressClaimState::Claiming && now >= self.deadline {
+            log::info!("claimed address {}", self.address);
+            self.state = AddressClaimState::Claimed;
+        }
+    }
+
+    /// First address of the dynamic range after ours that no other device claimed, starting at
+    /// `MIN_DYNAMIC_ADDRESS` when ours is below it
+    fn next_free_address(&self) -> Option<u8> {
+        let len = (MAX_ADDRESS - MIN_DYNAMIC_ADDRESS) as u16 + 1;
+        let (start, first) = match self.address.checked_sub(MIN_DYNAMIC_ADDRESS) {
+            Some(index) => (index as u16, 1),
+            None => (0, 0),
+        };
+        (first..first + len)
+            .map(|offset| MIN_DYNAMIC_ADDRESS + ((start + offset) % len) as u8)
+            .find(|address| !self.claimed.contains(*address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(arbitrary_address_capable: bool, identity_number: u32) -> Name {
+        Name::new(
+            arbitrary_address_capable,
+            4,
+            0,
+            0,
+            130,
+            0,
+            0,
+            2040,
+            identity_number,
+        )
+    }
+
+    #[test]
+    fn address_claim_process_claim() {
+        struct TestCase {
+            arbitrary_address_capable: bool,
+            source: u8,
+            other_name: Name,
+            send_claim: bool,
+            address: u8,
+            state: AddressClaimState,
+        }
+        let own = name(true, 100).value();
+        let test_cases = [
+            // Claim of another address
+            TestCase {
+                arbitrary_address_capable: true,
+                source: 11,
+                other_name: Name::from(own - 1),
+                send_claim: false,
+                address: 10,
+                state: AddressClaimState::Claiming,
+            },
+            // We have the lower NAME and defend the address
+            TestCase {
+                arbitrary_address_capable: true,
+                source: 10,
+                other_name: Name::from(own + 1),
+                send_claim: true,
+                address: 10,
+                state: AddressClaimState::Claiming,
+            },
+            // We lose and move to the dynamic range
+            TestCase {
+                arbitrary_address_capable: true,
+                source: 10,
+                other_name: Name::from(own - 1),
+                send_claim: true,
+                address: MIN_DYNAMIC_ADDRESS,
+                state: AddressClaimState::Claiming,
+            },
+            // We lose and can not move
+            TestCase {
+                arbitrary_address_capable: false,
+                source: 10,
+                other_name: Name::from(1),
+                send_claim: true,
+                address: NULL_ADDRESS,
+                state: AddressClaimState::CannotClaim,
+            },
+        ];
+        for i in &test_cases {
+            let mut claim = AddressClaim::new(name(i.arbitrary_address_capable, 100), 10, 0);
+            assert_eq!(i.send_claim, claim.process_claim(i.source, i.other_name, 0));
+            assert_eq!(i.address, claim.address());
+            assert_eq!(i.state, claim.state());
+        }
+    }
+
+    #[test]
+    fn address_claim_exhausted() {
+        let mut claim = AddressClaim::new(name(true, 100), 200, 0);
+        for address in (MIN_DYNAMIC_ADDRESS..MAX_ADDRESS).filter(|address| *address != 200) {
+            claim.process_claim(address, Name::from(1), 0);
+        }
+        assert_eq!(AddressClaimState::Claiming, claim.state());
+
+        // Moves on to the next free address, wrapping around within the dynamic range
+        assert!(claim.process_claim(200, Name::from(1), 0));
+        assert_eq!(MAX_ADDRESS, claim.address());
+
+        // Addresses below the dynamic range are free but not taken
+        assert!(claim.process_claim(MAX_ADDRESS, Name::from(1), 0));
+        assert_eq!(AddressClaimState::CannotClaim, claim.state());
+        assert_eq!(NULL_ADDRESS, claim.address());
+    }
+
+    #[test]
+    fn address_claim_poll() {
+        let mut claim = AddressClaim::new(name(true, 100), 10, 1000);
+        claim.poll(1000 + CLAIM_TIMEOUT - 1);
+        assert_eq!(AddressClaimState::Claiming, claim.state());
+        claim.poll(1000 + CLAIM_TIMEOUT);
+        assert_eq!(AddressClaimState::Claimed, claim.state());
+        assert_eq!(name(true, 100).to_bytes(), claim.claim_data());
+    }
+}
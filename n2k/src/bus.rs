@@ -1,19 +1,19 @@
 use core::{convert::TryFrom, fmt::Debug, marker::PhantomData};
 
-use heapless::FnvIndexMap;
+use heapless::{Deque, FnvIndexMap};
 
 use crate::{
+    address_claim::{AddressClaim, PGN_COMMANDED_ADDRESS, PGN_ISO_ADDRESS_CLAIM},
     fast_packet,
     hal_can::{self, Receiver, Transmitter},
     message::MessageError,
+    request::{self, AcknowledgementControl, PGN_ISO_ACKNOWLEDGEMENT, PGN_ISO_REQUEST},
+    transport::{self, AbortReason, ConnectionManagement, CB_TP_BAM, PGN_TP_CM, PGN_TP_DT},
 };
-use crate::{CanFrame, PgnRegistry};
-use crate::{Id, IdError, Message, GLOBAL_ADDRESS};
-
-const CB_TP_BAM: u8 = 0x40; // Control byte indicating TP_BAM
-
-const PGN_TP_CM: u32 = 0x00ec00; // 60416 - ISO Transport Protocol, Connection Management - RTS group
-const PGN_TP_DT: u32 = 0x00eb00; // 60160 - ISO Transport Protocol, Data Transfer
+use crate::{
+    AddressClaimState, CanFrame, Clock, CommandedAddressHook, Name, NoClock, PgnRegistry, Responder,
+};
+use crate::{Id, IdError, Message, Priority, GLOBAL_ADDRESS, MAX_ADDRESS};
 
 #[derive(Copy, Clone, Debug)]
 pub enum BusError<E, P> {
@@ -25,6 +25,11 @@ pub enum BusError<E, P> {
     MessageError(MessageError),
     OutOfFastPacketMemory,
     FastPacket(fast_packet::FastPacketError),
+    OutOfTransportMemory,
+    Transport(transport::TransportError),
+    TransmitQueueFull,
+    AddressNotClaimed,
+    ClockRequired,
     CanError(E),
     PgnError(P),
 }
@@ -46,6 +51,12 @@ impl<E, P> From<fast_packet::FastPacketError> for BusError<E, P> {
         BusError::FastPacket(error)
     }
 }
+
+impl<E, P> From<transport::TransportError> for BusError<E, P> {
+    fn from(error: transport::TransportError) -> Self {
+        BusError::Transport(error)
+    }
+}
 // impl<E> From<E> for BusError<E> {
 //     fn from(error: MessageError) -> Self {
 //         BusError::CanError(error)
@@ -54,35 +65,198 @@ impl<E, P> From<fast_packet::FastPacketError> for BusError<E, P> {
 
 pub type Result<T, E, P> = core::result::Result<T, BusError<E, P>>;
 
-pub struct Bus<T, P> {
+pub struct Bus<T, P, C = NoClock, R = (), H = ()> {
     can: T,
+    clock: C,
+    responder: R,
     address: u8,
+    // address claim procedure, None until a NAME is claimed for
+    address_claim: Option<AddressClaim>,
+    // approves commanded addresses
+    commanded_address_hook: H,
     // fast packet assembly cache, size must be a power of two, not currently enforced at compile time by FnvIndexMap
     fast_packet_cache:
         FnvIndexMap<fast_packet::FastPacketIdentifier, fast_packet::FastPacketCache, 16>,
+    // sequence counters of sent fast packets by PGN, oldest first
+    fast_packet_sequence: Deque<(u32, u8), 16>,
+    // transport protocol assembly sessions, size must be a power of two
+    transport_sessions: FnvIndexMap<transport::TransportIdentifier, transport::TransportSession, 4>,
+    // serial of the next transport session, the oldest is evicted when memory is full
+    transport_serial: u32,
+    // outgoing connection mode transfer
+    transport_send: Option<transport::SendSession>,
+    // frames generated while receiving, sent by poll()
+    tx_queue: Deque<CanFrame, 8>,
+    // requests received as (PGN, requester, addressed to us), answered by poll()
+    requests: Deque<(u32, u8, bool), 4>,
     _pgn_registry: PhantomData<P>,
 }
 
 impl<T, P> Bus<T, P> {
     pub fn new(can: T) -> Self {
+        Self::with_clock(can, NoClock)
+    }
+}
+
+impl<T, P, C> Bus<T, P, C> {
+    /// Creates a bus whose protocol timeouts are driven by `clock`
+    pub fn with_clock(can: T, clock: C) -> Self {
         Bus {
             can,
+            clock,
+            responder: (),
             address: 0,
+            address_claim: None,
+            commanded_address_hook: (),
             fast_packet_cache: FnvIndexMap::new(),
+            fast_packet_sequence: Deque::new(),
+            transport_sessions: FnvIndexMap::new(),
+            transport_serial: 0,
+            transport_send: None,
+            tx_queue: Deque::new(),
+            requests: Deque::new(),
+            _pgn_registry: PhantomData,
+        }
+    }
+}
+
+impl<T, P, C, H> Bus<T, P, C, (), H> {
+    /// Answers ISO Requests with `responder`, use a tuple to register several. Addressed
+    /// requests for PGNs no responder supports are NAKed.
+    pub fn with_responder<R: Responder>(self, responder: R) -> Bus<T, P, C, R, H> {
+        Bus {
+            can: self.can,
+            clock: self.clock,
+            responder,
+            address: self.address,
+            address_claim: self.address_claim,
+            commanded_address_hook: self.commanded_address_hook,
+            fast_packet_cache: self.fast_packet_cache,
+            fast_packet_sequence: self.fast_packet_sequence,
+            transport_sessions: self.transport_sessions,
+            transport_serial: self.transport_serial,
+            transport_send: self.transport_send,
+            tx_queue: self.tx_queue,
+            requests: self.requests,
+            _pgn_registry: PhantomData,
+        }
+    }
+}
+
+impl<T, P, C, R> Bus<T, P, C, R> {
+    /// Calls `hook` with the current and the new address when a 65240 Commanded Address for
+    /// our NAME is received, see `CommandedAddressHook`. Without a hook every commanded
+    /// address is accepted.
+    pub fn with_commanded_address_hook<H: CommandedAddressHook>(
+        self,
+        hook: H,
+    ) -> Bus<T, P, C, R, H> {
+        Bus {
+            can: self.can,
+            clock: self.clock,
+            responder: self.responder,
+            address: self.address,
+            address_claim: self.address_claim,
+            commanded_address_hook: hook,
+            fast_packet_cache: self.fast_packet_cache,
+            fast_packet_sequence: self.fast_packet_sequence,
+            transport_sessions: self.transport_sessions,
+            transport_serial: self.transport_serial,
+            transport_send: self.transport_send,
+            tx_queue: self.tx_queue,
+            requests: self.requests,
             _pgn_registry: PhantomData,
         }
     }
 }
 
-impl<T, E, I, F, P> Bus<T, P>
+impl<T, P, C, R, H> Bus<T, P, C, R, H> {
+    /// Source address used for sent messages
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// State of the address claim, None if no address was claimed
+    pub fn address_claim_state(&self) -> Option<AddressClaimState> {
+        self.address_claim.as_ref().map(|claim| claim.state())
+    }
+
+    fn address_claim_frame<E, PE>(claim: &AddressClaim) -> Result<CanFrame, E, PE> {
+        let id = Id::new(
+            Priority::Priority6,
+            PGN_ISO_ADDRESS_CLAIM,
+            claim.address(),
+            GLOBAL_ADDRESS,
+        )?;
+        Ok(CanFrame::new(id, &claim.claim_data()))
+    }
+
+    fn queue_frame<E, PE>(&mut self, frame: CanFrame) -> Result<(), E, PE> {
+        self.tx_queue
+            .push_back(frame)
+            .map_err(|_| BusError::TransmitQueueFull)
+    }
+
+    fn queue_tp_cm<E, PE>(
+        &mut self,
+        destination: u8,
+        cm: ConnectionManagement,
+    ) -> Result<(), E, PE> {
+        let id = Id::new(Priority::Priority7, PGN_TP_CM, self.address, destination)?;
+        self.queue_frame(CanFrame::new(id, &cm.encode()))
+    }
+
+    fn transport_session_id(
+        &self,
+        source: u8,
+        broadcast: bool,
+    ) -> Option<transport::TransportIdentifier> {
+        self.transport_sessions
+            .iter()
+            .find(|((session_source, _), session)| {
+                *session_source == source && session.is_broadcast() == broadcast
+            })
+            .map(|(session_id, _)| *session_id)
+    }
+
+    fn remove_transport_session(&mut self, source: u8, broadcast: bool) {
+        if let Some(session_id) = self.transport_session_id(source, broadcast) {
+            log::error!("aborting incomplete TP session {:?}", session_id);
+            self.transport_sessions.remove(&session_id);
+        }
+    }
+
+    /// Outgoing transfer the TP.CM frame `id` responds to
+    fn transport_send_for(&mut self, id: Id, pgn: u32) -> Option<&mut transport::SendSession> {
+        let address = self.address;
+        self.transport_send.as_mut().filter(|session| {
+            id.destination() == address
+                && session.id.destination() == id.source()
+                && session.id.pgn() == pgn
+        })
+    }
+}
+
+impl<T, E, I, F, P, C, R, H> Bus<T, P, C, R, H>
 where
     E: core::fmt::Debug,
     I: hal_can::Id<ExtendedId = u32>,
     F: hal_can::Frame<Id = I>,
     T: Receiver<Frame = F, Error = E>,
     P: PgnRegistry,
+    C: Clock,
+    H: CommandedAddressHook,
 {
     pub fn receive(&mut self) -> nb::Result<Option<P::Message>, BusError<E, P::Error>> {
+        self.receive_with(|_| {})
+    }
+
+    /// Like `receive`, additionally passes every complete message to `observer` before it is
+    /// built by the registry, e.g. to feed a `NetworkMap`
+    pub fn receive_with<O: FnMut(&Message)>(
+        &mut self,
+        mut observer: O,
+    ) -> nb::Result<Option<P::Message>, BusError<E, P::Error>> {
         // Consume at most one frame without blocking, propagate errors
         let frame = match self.can.receive() {
             Ok(frame) => frame,
@@ -101,6 +275,20 @@ where
         } else {
             return Err(BusError::NoData.into());
         };
+        // ISO transport protocol?
+        if id.pgn() == PGN_TP_CM {
+            return Ok(self.receive_tp_cm(id, data)?);
+        } else if id.pgn() == PGN_TP_DT {
+            return Ok(self.receive_tp_dt(id, data, &mut observer)?);
+        }
+
+        // Network management, the messages are still passed on
+        if id.pgn() == PGN_ISO_ADDRESS_CLAIM {
+            self.receive_address_claim(id, data)?;
+        } else if id.pgn() == PGN_ISO_REQUEST {
+            self.receive_request(id, data)?;
+        }
+
         // Is fast packet?
         if P::is_fast_packet(id.pgn()) {
             // Good explanation of the fast packet bit format:
@@ -140,8 +328,7 @@ where
                         self.fast_packet_cache.remove(&message_id);
                     } else if let Some(data) = cache.complete_data() {
                         // Packet is complete
-                        let message =
-                            P::build_message(id.pgn(), data).map_err(BusError::PgnError)?;
+                        let message = Self::build_message(id, id.pgn(), data, &mut observer)?;
                         self.fast_packet_cache.remove(&message_id);
                         return Ok(Some(message));
                     }
@@ -158,32 +345,356 @@ where
             Ok(None)
         } else {
             // Simple single-frame message
-            let message = P::build_message(id.pgn(), data).map_err(BusError::PgnError)?;
+            let message = Self::build_message(id, id.pgn(), data, &mut observer)?;
             Ok(Some(message))
         }
     }
+
+    fn receive_address_claim(&mut self, id: Id, data: &[u8]) -> Result<(), E, P::Error> {
+        let (claim, name) = match (self.address_claim.as_mut(), Name::try_from(data)) {
+            (Some(claim), Ok(name)) => (claim, name),
+            _ => return Ok(()),
+        };
+
+        let now = self.clock.now();
+        if claim.process_claim(id.source(), name, now) {
+            self.address = claim.address();
+            let frame = Self::address_claim_frame(claim)?;
+            self.queue_frame(frame)?;
+        }
+        Ok(())
+    }
+
+    fn receive_commanded_address(&mut self, name: Name, address: u8) -> Result<(), E, P::Error> {
+        let claim = match self.address_claim.as_mut() {
+            Some(claim) if claim.name() == name && address <= MAX_ADDRESS => claim,
+            _ => return Ok(()),
+        };
+        let current = self.address;
+        if !self.commanded_address_hook.accept(current, address) {
+            log::info!("commanded address {} rejected", address);
+            return Ok(());
+        }
+
+        claim.command(address, self.clock.now());
+        self.address = claim.address();
+        let frame = Self::address_claim_frame(claim)?;
+        self.queue_frame(frame)
+    }
+
+    fn receive_request(&mut self, id: Id, data: &[u8]) -> Result<(), E, P::Error> {
+        if data.len() < 3
+            || (id.destination() != GLOBAL_ADDRESS && id.destination() != self.address)
+        {
+            return Ok(());
+        }
+
+        let pgn = data[0] as u32 | (data[1] as u32) << 8 | (data[2] as u32) << 16;
+        match &self.address_claim {
+            Some(claim) if pgn == PGN_ISO_ADDRESS_CLAIM => {
+                let frame = Self::address_claim_frame(claim)?;
+                self.queue_frame(frame)
+            }
+            _ => {
+                let addressed = id.destination() != GLOBAL_ADDRESS;
+                self.requests
+                    .push_back((pgn, id.source(), addressed))
+                    .map_err(|_| BusError::TransmitQueueFull)
+            }
+        }
+    }
+
+    /// Starts reassembling a transfer. When memory is full the oldest session is evicted, as
+    /// sessions abandoned by their sender are only timed out with a clock.
+    fn insert_transport_session(
+        &mut self,
+        session_id: transport::TransportIdentifier,
+        mut session: transport::TransportSession,
+    ) -> Result<(), E, P::Error> {
+        let sessions = &self.transport_sessions;
+        if sessions.len() == sessions.capacity() && !sessions.contains_key(&session_id) {
+            let serial = self.transport_serial;
+            let oldest = sessions
+                .iter()
+                .max_by_key(|(_, session)| serial.wrapping_sub(session.serial))
+                .map(|(session_id, session)| (*session_id, session.is_broadcast()));
+            if let Some(((source, pgn), broadcast)) = oldest {
+                log::error!("evicting TP session {:?}", (source, pgn));
+                self.transport_sessions.remove(&(source, pgn));
+                if !broadcast {
+                    self.queue_tp_cm(
+                        source,
+                        ConnectionManagement::Abort {
+                            reason: AbortReason::ResourcesNeeded,
+                            pgn,
+                        },
+                    )?;
+                }
+            }
+        }
+
+        session.serial = self.transport_serial;
+        self.transport_serial = self.transport_serial.wrapping_add(1);
+        self.transport_sessions
+            .insert(session_id, session)
+            .map_err(|_| BusError::OutOfTransportMemory)?;
+        Ok(())
+    }
+
+    fn receive_tp_cm(&mut self, id: Id, data: &[u8]) -> Result<Option<P::Message>, E, P::Error> {
+        let now = self.clock.now();
+        match ConnectionManagement::decode(data) {
+            Some(ConnectionManagement::BroadcastAnnounce { size, packets, pgn }) => {
+                if id.destination() != GLOBAL_ADDRESS {
+                    log::error!("received TP.BAM with destination {}", id.destination());
+                    return Ok(None);
+                }
+                log::info!(
+                    "received TP.BAM PGN {}, size {}, packets {}",
+                    pgn,
+                    size,
+                    packets
+                );
+
+                // A new announcement replaces any broadcast still in progress from that source
+                self.remove_transport_session(id.source(), true);
+                let mut session = transport::TransportSession::new(size, packets)?;
+                session.deadline = now + transport::T1;
+                self.insert_transport_session((id.source(), pgn), session)?;
+            }
+            Some(ConnectionManagement::RequestToSend {
+                size,
+                packets,
+                max_packets,
+                pgn,
+            }) => {
+                if id.destination() != self.address {
+                    return Ok(None);
+                }
+                log::info!(
+                    "received TP.RTS PGN {}, size {}, packets {}",
+                    pgn,
+                    size,
+                    packets
+                );
+
+                // A new request replaces any transfer still in progress from that source
+                self.remove_transport_session(id.source(), false);
+                let mut session = match transport::TransportSession::new(size, packets) {
+                    Ok(session) => session,
+                    Err(e) => {
+                        self.queue_tp_cm(
+                            id.source(),
+                            ConnectionManagement::Abort {
+                                reason: AbortReason::ResourcesNeeded,
+                                pgn,
+                            },
+                        )?;
+                        return Err(e.into());
+                    }
+                };
+                session.max_packets = max_packets.max(1);
+                let window = session.next_window();
+                session.deadline = now + transport::T2;
+                self.insert_transport_session((id.source(), pgn), session)?;
+                self.queue_tp_cm(
+                    id.source(),
+                    ConnectionManagement::ClearToSend {
+                        packets: window,
+                        next_packet: 1,
+                        pgn,
+                    },
+                )?;
+            }
+            Some(ConnectionManagement::ClearToSend {
+                packets,
+                next_packet,
+                pgn,
+            }) => {
+                if let Some(session) = self.transport_send_for(id, pgn) {
+                    if packets == 0 || next_packet == 0 {
+                        // Receiver asks us to hold the transfer
+                        session.window_end = 0;
+                        session.deadline = Some(now + transport::T4);
+                    } else {
+                        // Packets of the window are sent by poll()
+                        session.next_packet = next_packet as usize;
+                        session.window_end = (next_packet as usize + packets as usize - 1)
+                            .min(session.total_packets());
+                        session.deadline = None;
+                    }
+                }
+            }
+            Some(ConnectionManagement::EndOfMessageAck { pgn, .. }) => {
+                if self.transport_send_for(id, pgn).is_some() {
+                    log::info!("TP transfer of PGN {} to {} complete", pgn, id.source());
+                    self.transport_send = None;
+                }
+            }
+            Some(ConnectionManagement::Abort { reason, pgn }) => {
+                if self.transport_send_for(id, pgn).is_some() {
+                    self.transport_send = None;
+                    return Err(transport::TransportError::Aborted(reason).into());
+                } else if id.destination() == self.address {
+                    log::error!("TP transfer of PGN {} aborted: {:?}", pgn, reason);
+                    self.transport_sessions.remove(&(id.source(), pgn));
+                }
+            }
+            None => {
+                log::info!("ignoring TP.CM frame {:?}", data);
+            }
+        }
+
+        Ok(None)
+    }
+
+    // `pgn` is passed separately as `id` cannot hold the low byte of PDU1 PGNs
+    fn build_message<O: FnMut(&Message)>(
+        id: Id,
+        pgn: u32,
+        data: &[u8],
+        observer: &mut O,
+    ) -> Result<P::Message, E, P::Error> {
+        observer(&Message::new(id, data)?);
+        P::build_message(pgn, data).map_err(BusError::PgnError)
+    }
+
+    fn receive_tp_dt<O: FnMut(&Message)>(
+        &mut self,
+        id: Id,
+        data: &[u8],
+        observer: &mut O,
+    ) -> Result<Option<P::Message>, E, P::Error> {
+        let broadcast = id.destination() == GLOBAL_ADDRESS;
+        if (!broadcast && id.destination() != self.address) || data.is_empty() {
+            return Ok(None);
+        }
+
+        // Data packets only carry the sequence number, the session is identified by its source
+        let session_id = match self.transport_session_id(id.source(), broadcast) {
+            Some(session_id) => session_id,
+            None => {
+                log::error!(
+                    "received TP.DT packet {} for unknown session from {}",
+                    data[0],
+                    id.source()
+                );
+                return Ok(None);
+            }
+        };
+
+        let now = self.clock.now();
+        let session = self.transport_sessions.get_mut(&session_id).unwrap();
+        match session.extend(data[0], &data[1..]) {
+            Err(e) => {
+                self.transport_sessions.remove(&session_id);
+                if !broadcast {
+                    self.queue_tp_cm(
+                        id.source(),
+                        ConnectionManagement::Abort {
+                            reason: AbortReason::BadSequenceNumber,
+                            pgn: session_id.1,
+                        },
+                    )?;
+                }
+                Err(e.into())
+            }
+            Ok(false) => {
+                if !broadcast && Some(session.last_sequence) == session.window_end {
+                    // Window complete, ask for the next one
+                    let cts = ConnectionManagement::ClearToSend {
+                        packets: session.next_window(),
+                        next_packet: session.last_sequence + 1,
+                        pgn: session_id.1,
+                    };
+                    session.deadline = now + transport::T2;
+                    self.queue_tp_cm(id.source(), cts)?;
+                } else {
+                    session.deadline = now + transport::T1;
+                }
+                Ok(None)
+            }
+            Ok(true) => {
+                // Packet is complete
+                let eoma = ConnectionManagement::EndOfMessageAck {
+                    size: session.total_size,
+                    packets: session.total_packets,
+                    pgn: session_id.1,
+                };
+                // PDU2 PGNs can only be addressed by the transport protocol itself
+                let message_id =
+                    Id::new(id.priority(), session_id.1, id.source(), id.destination()).or_else(
+                        |_| Id::new(id.priority(), session_id.1, id.source(), GLOBAL_ADDRESS),
+                    )?;
+                let data = session.complete_data().unwrap();
+                let commanded = match (session_id.1, data) {
+                    (PGN_COMMANDED_ADDRESS, [name @ .., address]) => {
+                        Name::try_from(name).ok().map(|name| (name, *address))
+                    }
+                    _ => None,
+                };
+                let message = Self::build_message(message_id, session_id.1, data, observer);
+                self.transport_sessions.remove(&session_id);
+                if !broadcast {
+                    self.queue_tp_cm(id.source(), eoma)?;
+                }
+                if let Some((name, address)) = commanded {
+                    self.receive_commanded_address(name, address)?;
+                }
+                message.map(Some)
+            }
+        }
+    }
 }
 
-impl<T, E, P> Bus<T, P>
+impl<T, E, P, C, R, H> Bus<T, P, C, R, H>
 where
     E: core::fmt::Debug,
     T: Transmitter<Frame = CanFrame, Error = E>,
     P: PgnRegistry,
+    C: Clock,
+    R: Responder,
 {
+    /// Starts claiming `address` for the device identified by `name`. Messages can be sent
+    /// once `poll` completed the claim, which requires a clock, see `Bus::with_clock`. If the
+    /// address is lost to a device with a lower NAME the bus moves on to the next free address
+    /// from `MIN_DYNAMIC_ADDRESS` up when `name` is arbitrary address capable.
+    pub fn claim_address(&mut self, name: Name, address: u8) -> Result<(), E, P::Error> {
+        // The claim would never complete
+        if !C::ADVANCES {
+            return Err(BusError::ClockRequired);
+        }
+        let claim = AddressClaim::new(name, address, self.clock.now());
+        let frame = Self::address_claim_frame(&claim)?;
+        self.address = claim.address();
+        self.address_claim = Some(claim);
+        self.transmit(&frame)
+    }
+
     pub fn send(&mut self, message: &Message) -> Result<(), E, P::Error> {
-        let id = message.id();
+        let mut id = message.id();
+        if let Some(claim) = &self.address_claim {
+            if claim.state() != AddressClaimState::Claimed {
+                return Err(BusError::AddressNotClaimed);
+            }
+            // Our address may have changed since the message was created
+            id = Id::new(id.priority(), id.pgn(), self.address, id.destination())?;
+        }
         let data = message.data();
         let length = data.len();
 
-        if length <= 8 {
-            //TODO: Make sure it's not a fast packet
+        if P::is_fast_packet(id.pgn()) {
+            self.send_fast_packet(id, data)
+        } else if length <= 8 {
             let frame = CanFrame::new(id, data);
             self.transmit(&frame)?;
             Ok(())
+        } else if id.destination() != GLOBAL_ADDRESS {
+            self.send_connection(id, data)
         } else {
             // Send a broadcast ISO 11783 multi-packet
             //calculate number of packets that will be sent
-            let packets = (length / 7) + 1;
+            let packets = length.div_ceil(7);
             // send broadcast announce message (BAM)
             let pgn = id.pgn();
             let priority = id.priority();
@@ -233,6 +744,180 @@ where
         }
     }
 
+    /// Starts an addressed ISO 11783 multi-packet transfer, its packets are sent by `poll` as
+    /// the destination grants them
+    fn send_connection(&mut self, id: Id, data: &[u8]) -> Result<(), E, P::Error> {
+        if self.transport_send.is_some() {
+            return Err(transport::TransportError::Busy.into());
+        }
+
+        let session = transport::SendSession::new(id, data, self.clock.now())?;
+        let tp_cm_id = Id::new(id.priority(), PGN_TP_CM, self.address, id.destination())?;
+        let frame = CanFrame::new(tp_cm_id, &session.request_to_send().encode());
+        self.transport_send = Some(session);
+        self.transmit(&frame)
+    }
+
+    /// Transmits frames queued while receiving, answers ISO Requests, sends the packets of
+    /// addressed transfers and expires stalled transport protocol sessions and address claims.
+    /// Call this regularly.
+    pub fn poll(&mut self) -> Result<(), E, P::Error> {
+        let now = self.clock.now();
+
+        if let Some(claim) = self.address_claim.as_mut() {
+            claim.poll(now);
+        }
+
+        while let Some(frame) = self.tx_queue.pop_front() {
+            self.transmit(&frame)?;
+        }
+
+        while let Some((pgn, requester, addressed)) = self.requests.pop_front() {
+            self.respond(pgn, requester, addressed)?;
+        }
+
+        // Incoming transfers whose sender went silent
+        let expired: heapless::Vec<_, 4> = self
+            .transport_sessions
+            .iter()
+            .filter(|(_, session)| now > session.deadline)
+            .map(|(session_id, session)| (*session_id, session.is_broadcast()))
+            .collect();
+        for ((source, pgn), broadcast) in expired {
+            log::error!("TP session {:?} timed out", (source, pgn));
+            self.transport_sessions.remove(&(source, pgn));
+            if !broadcast {
+                let tp_cm_id = Id::new(Priority::Priority7, PGN_TP_CM, self.address, source)?;
+                let abort = ConnectionManagement::Abort {
+                    reason: AbortReason::Timeout,
+                    pgn,
+                };
+                self.transmit(&CanFrame::new(tp_cm_id, &abort.encode()))?;
+            }
+        }
+
+        // Outgoing transfer whose receiver went silent
+        if let Some(session) = &self.transport_send {
+            if session.deadline.is_some_and(|deadline| now > deadline) {
+                let tp_cm_id = Id::new(
+                    Priority::Priority7,
+                    PGN_TP_CM,
+                    self.address,
+                    session.id.destination(),
+                )?;
+                let abort = ConnectionManagement::Abort {
+                    reason: AbortReason::Timeout,
+                    pgn: session.id.pgn(),
+                };
+                self.transport_send = None;
+                self.transmit(&CanFrame::new(tp_cm_id, &abort.encode()))?;
+                return Err(transport::TransportError::Timeout.into());
+            }
+        }
+
+        while let Some(frame) = self.next_transport_packet(now)? {
+            self.transmit(&frame)?;
+        }
+
+        Ok(())
+    }
+
+    fn respond(&mut self, pgn: u32, requester: u8, addressed: bool) -> Result<(), E, P::Error> {
+        if self
+            .address_claim
+            .as_ref()
+            .is_some_and(|claim| claim.state() != AddressClaimState::Claimed)
+        {
+            log::info!("ignoring request for PGN {} without an address", pgn);
+            return Ok(());
+        }
+
+        let destination = if addressed { requester } else { GLOBAL_ADDRESS };
+        let mut buf = [0; fast_packet::MAX_FAST_PACKET_SIZE];
+        let mut index = 0;
+        while let Some(length) = self.responder.respond(pgn, index, &mut buf) {
+            // PDU2 PGNs can only be broadcast
+            let id = Id::new(Priority::Priority6, pgn, self.address, destination)
+                .or_else(|_| Id::new(Priority::Priority6, pgn, self.address, GLOBAL_ADDRESS))?;
+            self.send(&Message::new(id, &buf[..length])?)?;
+            index += 1;
+        }
+
+        // Only addressed requests are NAKed
+        if index == 0 && addressed {
+            log::info!("NAK request for PGN {} from {}", pgn, requester);
+            let id = Id::new(
+                Priority::Priority6,
+                PGN_ISO_ACKNOWLEDGEMENT,
+                self.address,
+                GLOBAL_ADDRESS,
+            )?;
+            let nak = request::acknowledgement(AcknowledgementControl::Nak, requester, pgn);
+            self.transmit(&CanFrame::new(id, &nak))?;
+        }
+        Ok(())
+    }
+
+    fn next_transport_packet(&mut self, now: u64) -> Result<Option<CanFrame>, E, P::Error> {
+        let address = self.address;
+        let session = match self.transport_send.as_mut() {
+            Some(session) if session.has_pending_packets() => session,
+            _ => return Ok(None),
+        };
+
+        let tp_dt_id = Id::new(
+            Priority::Priority7,
+            PGN_TP_DT,
+            address,
+            session.id.destination(),
+        )?;
+        let frame = CanFrame::new(tp_dt_id, &session.packet(session.next_packet));
+        session.next_packet += 1;
+        if !session.has_pending_packets() {
+            // Window sent, wait for the next CTS or the EndOfMsgAck
+            session.deadline = Some(now + transport::T3);
+        }
+
+        Ok(Some(frame))
+    }
+
+    fn send_fast_packet(&mut self, id: Id, data: &[u8]) -> Result<(), E, P::Error> {
+        // Every PGN has its own 3 bit sequence counter, so receivers can tell consecutive fast
+        // packets of the same PGN apart. The counter of the PGN sent first is dropped when
+        // memory is full, it restarts at 0 if that PGN is sent again.
+        let pgn = id.pgn();
+        let sequence = match self
+            .fast_packet_sequence
+            .iter_mut()
+            .find(|(sequence_pgn, _)| *sequence_pgn == pgn)
+        {
+            Some((_, sequence)) => {
+                *sequence = (*sequence + 1) & 0x07;
+                *sequence
+            }
+            None => {
+                if self.fast_packet_sequence.is_full() {
+                    self.fast_packet_sequence.pop_front();
+                }
+                self.fast_packet_sequence.push_back((pgn, 0)).ok();
+                0
+            }
+        };
+
+        log::info!(
+            "sending fast packet PGN {}, sequence {}, size {}",
+            id.pgn(),
+            sequence,
+            data.len()
+        );
+        for fp_data in fast_packet::FastPacketFrames::new(sequence, data)? {
+            let frame = CanFrame::new(id, &fp_data);
+            self.transmit(&frame)?;
+        }
+
+        Ok(())
+    }
+
     fn transmit(&mut self, frame: &CanFrame) -> Result<(), E, P::Error> {
         // TODO: revise this as it's not looking optimal or correct
         let result = self.can.transmit(frame);
@@ -256,20 +941,29 @@ where
 #[cfg(test)]
 mod tests {
     extern crate alloc;
-    use alloc::boxed::Box;
+    use alloc::collections::VecDeque;
+    use alloc::rc::Rc;
     use alloc::vec::Vec;
+    use core::cell::Cell;
 
     use crate::hal_can::{Filter, Frame, Interface, Receiver, Transmitter};
-    use crate::{Bus, Id, Message, Priority, GLOBAL_ADDRESS};
+    use crate::{
+        AbortReason, AddressClaimState, Bus, BusError, Clock, CommandedAddressHook, Id, Message,
+        Name, PgnList, PgnRegistry, Priority, Product, TransportError, GLOBAL_ADDRESS,
+    };
 
     use crate::frame::*;
     struct MockCan {
         pub frames: Vec<CanFrame>,
+        pub received: VecDeque<CanFrame>,
     }
 
     impl MockCan {
         pub fn new() -> Self {
-            MockCan { frames: Vec::new() }
+            MockCan {
+                frames: Vec::new(),
+                received: VecDeque::new(),
+            }
         }
     }
 
@@ -300,7 +994,7 @@ mod tests {
 
     impl Receiver for MockCan {
         fn receive(&mut self) -> nb::Result<Self::Frame, Self::Error> {
-            panic!();
+            self.received.pop_front().ok_or(nb::Error::WouldBlock)
         }
 
         fn set_filter(&mut self, _filter: Self::Filter) {
@@ -319,44 +1013,826 @@ mod tests {
         }
     }
 
+    #[derive(Clone, Default)]
+    struct MockClock(Rc<Cell<u64>>);
+
+    impl MockClock {
+        fn advance(&self, ms: u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> u64 {
+            self.0.get()
+        }
+    }
+
+    struct MockRegistry {}
+
+    impl PgnRegistry for MockRegistry {
+        type Message = (u32, Vec<u8>);
+        type Error = ();
+
+        fn is_fast_packet(pgn: u32) -> bool {
+            matches!(pgn, 126996 | 129029 | 130000..=130016)
+        }
+
+        fn build_message(pgn: u32, data: &[u8]) -> Result<Self::Message, Self::Error> {
+            Ok((pgn, data.to_vec()))
+        }
+    }
+
     #[test]
     fn bus_send() {
-        struct TestCase {
-            message: Message,
+        struct TestCase<'a> {
+            message: Message<'a>,
         }
         let test_cases = [
             TestCase {
                 message: Message::new(
                     Id::new(Priority::Priority0, 12345, 123, GLOBAL_ADDRESS).unwrap(),
-                    Box::new([1, 2, 3, 4, 5, 6, 7]),
+                    &[1, 2, 3, 4, 5, 6, 7],
                 )
                 .unwrap(),
             },
             TestCase {
                 message: Message::new(
                     Id::new(Priority::Priority0, 12345, 123, GLOBAL_ADDRESS).unwrap(),
-                    Box::new([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17]),
+                    &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17],
                 )
                 .unwrap(),
             },
         ];
         for i in &test_cases {
             let can = MockCan::new();
-            let mut bus = Bus::new(can);
+            let mut bus: Bus<_, MockRegistry> = Bus::new(can);
 
             bus.send(&i.message).unwrap();
 
             let data = i.message.data();
             if data.len() <= 8 {
                 // Single packet
+                assert_eq!(bus.can.frames.len(), 1);
+                assert_eq!(bus.can.frames[0].data().unwrap(), data);
             } else {
                 // Multipacket
-                for b in 0..data.len() {
+                for (b, byte) in data.iter().enumerate() {
                     let frame = (b / 7) + 1;
                     let index = b - ((frame - 1) * 7) + 1;
-                    assert_eq!(bus.can.frames[frame].data().unwrap()[index], data[b])
+                    assert_eq!(bus.can.frames[frame].data().unwrap()[index], *byte)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bus_send_fast_packet() {
+        let data: Vec<u8> = (0..43).collect();
+        let message = Message::new(
+            Id::new(Priority::Priority3, 129029, 123, GLOBAL_ADDRESS).unwrap(),
+            &data,
+        )
+        .unwrap();
+
+        let can = MockCan::new();
+        let mut bus: Bus<_, MockRegistry> = Bus::new(can);
+        bus.send(&message).unwrap();
+
+        // 6 bytes in the first frame, 7 bytes in each following frame
+        let frames = &bus.can.frames;
+        assert_eq!(frames.len(), 7);
+        assert!(frames.iter().all(|f| f.id().pgn() == 129029));
+        assert_eq!(frames[0].data().unwrap(), &[0x00, 43, 0, 1, 2, 3, 4, 5]);
+        assert_eq!(frames[1].data().unwrap(), &[0x01, 6, 7, 8, 9, 10, 11, 12]);
+        assert_eq!(
+            frames[6].data().unwrap(),
+            &[0x06, 41, 42, 0xff, 0xff, 0xff, 0xff, 0xff]
+        );
+
+        // The sequence counter advances per PGN and wraps after 7
+        for _ in 0..8 {
+            bus.send(&message).unwrap();
+        }
+        let sequences: Vec<u8> = bus
+            .can
+            .frames
+            .iter()
+            .filter(|f| f.data().unwrap()[0] & 0x1f == 0)
+            .map(|f| f.data().unwrap()[0] >> 5)
+            .collect();
+        assert_eq!(sequences, [0, 1, 2, 3, 4, 5, 6, 7, 0]);
+    }
+
+    #[test]
+    fn bus_send_fast_packet_sequence_eviction() {
+        let data = [0; 10];
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        let send = |bus: &mut Bus<MockCan, MockRegistry>, pgn| {
+            let id = Id::new(Priority::Priority3, pgn, 123, GLOBAL_ADDRESS).unwrap();
+            bus.send(&Message::new(id, &data).unwrap()).unwrap();
+            bus.can.frames.last().unwrap().data().unwrap()[0] >> 5
+        };
+
+        // More PGNs than counters, the counter of the first one is dropped
+        assert_eq!(0, send(&mut bus, 130000));
+        assert_eq!(1, send(&mut bus, 130000));
+        for pgn in 130001..130016 {
+            assert_eq!(0, send(&mut bus, pgn));
+        }
+        assert_eq!(2, send(&mut bus, 130000));
+        assert_eq!(0, send(&mut bus, 130016));
+        assert_eq!(0, send(&mut bus, 130000));
+        assert_eq!(1, send(&mut bus, 130015));
+    }
+
+    #[test]
+    fn bus_send_short_fast_packet() {
+        let message = Message::new(
+            Id::new(Priority::Priority6, 126996, 123, GLOBAL_ADDRESS).unwrap(),
+            &[1, 2, 3],
+        )
+        .unwrap();
+
+        let can = MockCan::new();
+        let mut bus: Bus<_, MockRegistry> = Bus::new(can);
+        bus.send(&message).unwrap();
+
+        assert_eq!(bus.can.frames.len(), 1);
+        assert_eq!(
+            bus.can.frames[0].data().unwrap(),
+            &[0x00, 3, 1, 2, 3, 0xff, 0xff, 0xff]
+        );
+    }
+
+    type ReceiveResult = Result<Option<(u32, Vec<u8>)>, BusError<(), ()>>;
+
+    fn receive_all<C: Clock, R, H: CommandedAddressHook>(
+        bus: &mut Bus<MockCan, MockRegistry, C, R, H>,
+        frames: &[CanFrame],
+    ) -> Vec<ReceiveResult> {
+        bus.can.received.extend(frames.iter().cloned());
+        frames
+            .iter()
+            .map(|_| {
+                bus.receive().map_err(|e| match e {
+                    nb::Error::Other(e) => e,
+                    nb::Error::WouldBlock => panic!(),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bus_receive_tp_bam() {
+        let data: Vec<u8> = (0..20).collect();
+        let cm = Id::new(Priority::Priority7, 60416, 61, GLOBAL_ADDRESS).unwrap();
+        let dt = Id::new(Priority::Priority7, 60160, 61, GLOBAL_ADDRESS).unwrap();
+        let frames = [
+            CanFrame::new(cm, &[0x20, 20, 0, 3, 0xff, 0x00, 0xee, 0x01]),
+            CanFrame::new(dt, &[1, 0, 1, 2, 3, 4, 5, 6]),
+            CanFrame::new(dt, &[2, 7, 8, 9, 10, 11, 12, 13]),
+            CanFrame::new(dt, &[3, 14, 15, 16, 17, 18, 19, 0xff]),
+        ];
+
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        let results = receive_all(&mut bus, &frames);
+
+        assert!(results[..3].iter().all(|r| matches!(r, Ok(None))));
+        assert_eq!(results[3].as_ref().unwrap(), &Some((126464, data)));
+        assert!(bus.transport_sessions.is_empty());
+    }
+
+    #[test]
+    fn bus_receive_tp_bam_sequence_error() {
+        let cm = Id::new(Priority::Priority7, 60416, 61, GLOBAL_ADDRESS).unwrap();
+        let dt = Id::new(Priority::Priority7, 60160, 61, GLOBAL_ADDRESS).unwrap();
+        let frames = [
+            CanFrame::new(cm, &[0x20, 20, 0, 3, 0xff, 0x00, 0xee, 0x01]),
+            CanFrame::new(dt, &[1, 0, 1, 2, 3, 4, 5, 6]),
+            CanFrame::new(dt, &[3, 14, 15, 16, 17, 18, 19, 0xff]),
+        ];
+
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        let results = receive_all(&mut bus, &frames);
+
+        assert!(matches!(
+            results[2],
+            Err(BusError::Transport(
+                crate::transport::TransportError::UnexpectedSequenceNumber {
+                    sequence: 3,
+                    expected: 2
+                }
+            ))
+        ));
+        assert!(bus.transport_sessions.is_empty());
+    }
+
+    #[test]
+    fn bus_send_receive_tp_bam() {
+        let data: Vec<u8> = (0..21).collect();
+        let message = Message::new(
+            Id::new(Priority::Priority6, 65280, 0, GLOBAL_ADDRESS).unwrap(),
+            &data,
+        )
+        .unwrap();
+
+        let mut sender: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        sender.send(&message).unwrap();
+        assert_eq!(sender.can.frames.len(), 4);
+
+        let mut receiver: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        let results = receive_all(&mut receiver, &sender.can.frames);
+        assert_eq!(results[3].as_ref().unwrap(), &Some((65280, data)));
+    }
+
+    fn take_sent<C, R, H>(bus: &mut Bus<MockCan, MockRegistry, C, R, H>) -> Vec<(Id, Vec<u8>)> {
+        bus.can
+            .frames
+            .drain(..)
+            .map(|f| (f.id(), f.data().unwrap().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn bus_receive_tp_connection() {
+        let data: Vec<u8> = (0..20).collect();
+        let cm = Id::new(Priority::Priority7, 60416, 35, 0).unwrap();
+        let dt = Id::new(Priority::Priority7, 60160, 35, 0).unwrap();
+
+        // Sender allows 2 packets per CTS
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        let results = receive_all(
+            &mut bus,
+            &[
+                CanFrame::new(cm, &[0x10, 20, 0, 3, 2, 0x76, 0xef, 0x01]),
+                CanFrame::new(dt, &[1, 0, 1, 2, 3, 4, 5, 6]),
+                CanFrame::new(dt, &[2, 7, 8, 9, 10, 11, 12, 13]),
+            ],
+        );
+        assert!(results.iter().all(|r| matches!(r, Ok(None))));
+        bus.poll().unwrap();
+
+        let sent = take_sent(&mut bus);
+        assert_eq!(sent.len(), 2);
+        assert!(sent
+            .iter()
+            .all(|(id, _)| id.pgn() == 60416 && id.destination() == 35));
+        assert_eq!(sent[0].1, [0x11, 2, 1, 0xff, 0xff, 0x76, 0xef, 0x01]);
+        assert_eq!(sent[1].1, [0x11, 1, 3, 0xff, 0xff, 0x76, 0xef, 0x01]);
+
+        let results = receive_all(
+            &mut bus,
+            &[CanFrame::new(dt, &[3, 14, 15, 16, 17, 18, 19, 0xff])],
+        );
+        assert_eq!(results[0].as_ref().unwrap(), &Some((126838, data)));
+        bus.poll().unwrap();
+
+        let sent = take_sent(&mut bus);
+        assert_eq!(sent[0].1, [0x13, 20, 0, 3, 0xff, 0x76, 0xef, 0x01]);
+    }
+
+    #[test]
+    fn bus_receive_tp_connection_other_destination() {
+        let cm = Id::new(Priority::Priority7, 60416, 35, 36).unwrap();
+
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        receive_all(
+            &mut bus,
+            &[CanFrame::new(cm, &[0x10, 20, 0, 3, 2, 0x76, 0xef, 0x01])],
+        );
+        bus.poll().unwrap();
+
+        assert!(bus.can.frames.is_empty());
+        assert!(bus.transport_sessions.is_empty());
+    }
+
+    #[test]
+    fn bus_receive_tp_connection_timeout() {
+        let clock = MockClock::default();
+        let cm = Id::new(Priority::Priority7, 60416, 35, 0).unwrap();
+
+        let mut bus: Bus<_, MockRegistry, _> = Bus::with_clock(MockCan::new(), clock.clone());
+        receive_all(
+            &mut bus,
+            &[CanFrame::new(cm, &[0x10, 20, 0, 3, 0xff, 0x76, 0xef, 0x01])],
+        );
+        bus.poll().unwrap();
+        take_sent(&mut bus);
+
+        clock.advance(1251);
+        bus.poll().unwrap();
+
+        let sent = take_sent(&mut bus);
+        assert_eq!(sent[0].1, [0xff, 3, 0xff, 0xff, 0xff, 0x76, 0xef, 0x01]);
+        assert!(bus.transport_sessions.is_empty());
+    }
+
+    #[test]
+    fn bus_receive_tp_session_eviction() {
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        // Abandoned transfers, without a clock they never time out
+        for source in 30..34 {
+            let cm = Id::new(Priority::Priority7, 60416, source, 0).unwrap();
+            receive_all(
+                &mut bus,
+                &[CanFrame::new(cm, &[0x10, 20, 0, 3, 0xff, 0x76, 0xef, 0x01])],
+            );
+        }
+        let bam = Id::new(Priority::Priority7, 60416, 61, GLOBAL_ADDRESS).unwrap();
+        receive_all(
+            &mut bus,
+            &[CanFrame::new(
+                bam,
+                &[0x20, 20, 0, 3, 0xff, 0x00, 0xee, 0x01],
+            )],
+        );
+        bus.poll().unwrap();
+
+        // The first transfer is aborted to make room for the broadcast
+        let sent = take_sent(&mut bus);
+        let (id, data) = sent.last().unwrap();
+        assert_eq!(30, id.destination());
+        assert_eq!(data, &[0xff, 2, 0xff, 0xff, 0xff, 0x76, 0xef, 0x01]);
+        assert_eq!(4, bus.transport_sessions.len());
+        assert!(!bus.transport_sessions.contains_key(&(30, 126838)));
+
+        let dt = Id::new(Priority::Priority7, 60160, 61, GLOBAL_ADDRESS).unwrap();
+        let results = receive_all(
+            &mut bus,
+            &[
+                CanFrame::new(dt, &[1, 0, 1, 2, 3, 4, 5, 6]),
+                CanFrame::new(dt, &[2, 7, 8, 9, 10, 11, 12, 13]),
+                CanFrame::new(dt, &[3, 14, 15, 16, 17, 18, 19, 0xff]),
+            ],
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap(),
+            &Some((126464, (0..20).collect()))
+        );
+
+        // Sessions are evicted in the order they were opened
+        for source in 34..37 {
+            let cm = Id::new(Priority::Priority7, 60416, source, 0).unwrap();
+            receive_all(
+                &mut bus,
+                &[CanFrame::new(cm, &[0x10, 20, 0, 3, 0xff, 0x76, 0xef, 0x01])],
+            );
+        }
+        let mut sources: Vec<u8> = bus.transport_sessions.keys().map(|(s, _)| *s).collect();
+        sources.sort();
+        assert_eq!(sources, [33, 34, 35, 36]);
+    }
+
+    #[test]
+    fn bus_send_tp_connection() {
+        let clock = MockClock::default();
+        let data: Vec<u8> = (0..20).collect();
+        let message = Message::new(
+            Id::new(Priority::Priority6, 126720 & 0x1ff00, 0, 35).unwrap(),
+            &data,
+        )
+        .unwrap();
+        let cm = Id::new(Priority::Priority7, 60416, 35, 0).unwrap();
+
+        let mut bus: Bus<_, MockRegistry, _> = Bus::with_clock(MockCan::new(), clock.clone());
+        bus.send(&message).unwrap();
+        assert!(matches!(
+            bus.send(&message),
+            Err(BusError::Transport(TransportError::Busy))
+        ));
+
+        let sent = take_sent(&mut bus);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].0.destination(), 35);
+        assert_eq!(sent[0].1, [0x10, 20, 0, 3, 0xff, 0x00, 0xef, 0x01]);
+
+        // Nothing is sent before the receiver grants a window
+        bus.poll().unwrap();
+        assert!(bus.can.frames.is_empty());
+
+        receive_all(
+            &mut bus,
+            &[CanFrame::new(
+                cm,
+                &[0x11, 2, 1, 0xff, 0xff, 0x00, 0xef, 0x01],
+            )],
+        );
+        bus.poll().unwrap();
+        let sent = take_sent(&mut bus);
+        assert_eq!(sent.len(), 2);
+        assert!(sent
+            .iter()
+            .all(|(id, _)| id.pgn() == 60160 && id.destination() == 35));
+        assert_eq!(sent[0].1, [1, 0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(sent[1].1, [2, 7, 8, 9, 10, 11, 12, 13]);
+
+        // Hold, then continue within T4
+        receive_all(
+            &mut bus,
+            &[CanFrame::new(
+                cm,
+                &[0x11, 0, 0xff, 0xff, 0xff, 0x00, 0xef, 0x01],
+            )],
+        );
+        clock.advance(1000);
+        bus.poll().unwrap();
+        assert!(bus.can.frames.is_empty());
+        receive_all(
+            &mut bus,
+            &[CanFrame::new(
+                cm,
+                &[0x11, 1, 3, 0xff, 0xff, 0x00, 0xef, 0x01],
+            )],
+        );
+        bus.poll().unwrap();
+        let sent = take_sent(&mut bus);
+        assert_eq!(sent[0].1, [3, 14, 15, 16, 17, 18, 19, 0xff]);
+
+        receive_all(
+            &mut bus,
+            &[CanFrame::new(cm, &[0x13, 20, 0, 3, 0xff, 0x00, 0xef, 0x01])],
+        );
+        assert!(bus.transport_send.is_none());
+        clock.advance(5000);
+        bus.poll().unwrap();
+        assert!(bus.can.frames.is_empty());
+    }
+
+    #[test]
+    fn bus_send_tp_connection_timeout() {
+        let clock = MockClock::default();
+        let data: Vec<u8> = (0..20).collect();
+        let message = Message::new(
+            Id::new(Priority::Priority6, 126720 & 0x1ff00, 0, 35).unwrap(),
+            &data,
+        )
+        .unwrap();
+
+        let mut bus: Bus<_, MockRegistry, _> = Bus::with_clock(MockCan::new(), clock.clone());
+        bus.send(&message).unwrap();
+        take_sent(&mut bus);
+
+        clock.advance(1250);
+        bus.poll().unwrap();
+        clock.advance(1);
+        assert!(matches!(
+            bus.poll(),
+            Err(BusError::Transport(TransportError::Timeout))
+        ));
+
+        let sent = take_sent(&mut bus);
+        assert_eq!(sent[0].1, [0xff, 3, 0xff, 0xff, 0xff, 0x00, 0xef, 0x01]);
+        assert!(bus.transport_send.is_none());
+    }
+
+    #[test]
+    fn bus_send_tp_connection_aborted() {
+        let data: Vec<u8> = (0..20).collect();
+        let message = Message::new(
+            Id::new(Priority::Priority6, 126720 & 0x1ff00, 0, 35).unwrap(),
+            &data,
+        )
+        .unwrap();
+        let cm = Id::new(Priority::Priority7, 60416, 35, 0).unwrap();
+
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        bus.send(&message).unwrap();
+
+        let results = receive_all(
+            &mut bus,
+            &[CanFrame::new(
+                cm,
+                &[0xff, 1, 0xff, 0xff, 0xff, 0x00, 0xef, 0x01],
+            )],
+        );
+        assert!(matches!(
+            results[0],
+            Err(BusError::Transport(TransportError::Aborted(
+                AbortReason::AlreadyInSession
+            )))
+        ));
+        assert!(bus.transport_send.is_none());
+    }
+
+    fn test_name(arbitrary_address_capable: bool) -> Name {
+        Name::new(arbitrary_address_capable, 4, 0, 0, 130, 0, 0, 2040, 100)
+    }
+
+    #[test]
+    fn bus_address_claim_no_clock() {
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        assert!(matches!(
+            bus.claim_address(test_name(true), 35),
+            Err(BusError::ClockRequired)
+        ));
+        assert!(bus.can.frames.is_empty());
+        assert_eq!(None, bus.address_claim_state());
+    }
+
+    #[test]
+    fn bus_address_claim() {
+        let clock = MockClock::default();
+        let mut bus: Bus<_, MockRegistry, _> = Bus::with_clock(MockCan::new(), clock.clone());
+        let name = test_name(true);
+        bus.claim_address(name, 35).unwrap();
+
+        let sent = take_sent(&mut bus);
+        assert_eq!(1, sent.len());
+        assert_eq!(60928, sent[0].0.pgn());
+        assert_eq!(35, sent[0].0.source());
+        assert_eq!(GLOBAL_ADDRESS, sent[0].0.destination());
+        assert_eq!(name.to_bytes().to_vec(), sent[0].1);
+
+        // Messages are held back until the claim went uncontested for 250ms
+        let data = [1, 2, 3];
+        let message = Message::new(
+            Id::new(Priority::Priority2, 127250, 0, GLOBAL_ADDRESS).unwrap(),
+            &data,
+        )
+        .unwrap();
+        assert!(matches!(
+            bus.send(&message),
+            Err(BusError::AddressNotClaimed)
+        ));
+        clock.advance(250);
+        bus.poll().unwrap();
+        assert_eq!(Some(AddressClaimState::Claimed), bus.address_claim_state());
+
+        // The source of sent messages is replaced with the claimed address
+        bus.send(&message).unwrap();
+        let sent = take_sent(&mut bus);
+        assert_eq!(1, sent.len());
+        assert_eq!(35, sent[0].0.source());
+        assert_eq!(127250, sent[0].0.pgn());
+
+        // Requests for the address claim are answered, other requests are not
+        let request = Id::new(Priority::Priority6, 59904, 12, GLOBAL_ADDRESS).unwrap();
+        let results = receive_all(
+            &mut bus,
+            &[
+                CanFrame::new(request, &[0x00, 0xee, 0x00]),
+                CanFrame::new(request, &[0x14, 0xf0, 0x01]),
+            ],
+        );
+        assert!(results.iter().all(|r| matches!(r, Ok(Some(_)))));
+        bus.poll().unwrap();
+        let sent = take_sent(&mut bus);
+        assert_eq!(1, sent.len());
+        assert_eq!(60928, sent[0].0.pgn());
+        assert_eq!(35, sent[0].0.source());
+    }
+
+    #[test]
+    fn bus_address_claim_contention() {
+        struct TestCase {
+            arbitrary_address_capable: bool,
+            lower_name: bool,
+            address: u8,
+            state: AddressClaimState,
+        }
+        let test_cases = [
+            // We have the lower NAME and defend our address
+            TestCase {
+                arbitrary_address_capable: true,
+                lower_name: true,
+                address: 135,
+                state: AddressClaimState::Claimed,
+            },
+            // We lose and move on to the next free address
+            TestCase {
+                arbitrary_address_capable: true,
+                lower_name: false,
+                address: 137,
+                state: AddressClaimState::Claiming,
+            },
+            // We lose and can not move, "cannot claim" is sent from the null address
+            TestCase {
+                arbitrary_address_capable: false,
+                lower_name: false,
+                address: 254,
+                state: AddressClaimState::CannotClaim,
+            },
+        ];
+        for i in &test_cases {
+            let clock = MockClock::default();
+            let mut bus: Bus<_, MockRegistry, _> = Bus::with_clock(MockCan::new(), clock.clone());
+            let name = test_name(i.arbitrary_address_capable);
+            let other_name = if i.lower_name {
+                name.value() + 1
+            } else {
+                name.value() - 1
+            };
+            bus.claim_address(name, 135).unwrap();
+            clock.advance(250);
+            bus.poll().unwrap();
+            take_sent(&mut bus);
+
+            let results = receive_all(
+                &mut bus,
+                &[
+                    CanFrame::new(
+                        Id::new(Priority::Priority6, 60928, 136, GLOBAL_ADDRESS).unwrap(),
+                        &(name.value() - 2).to_le_bytes(),
+                    ),
+                    CanFrame::new(
+                        Id::new(Priority::Priority6, 60928, 135, GLOBAL_ADDRESS).unwrap(),
+                        &other_name.to_le_bytes(),
+                    ),
+                ],
+            );
+            assert!(results.iter().all(|r| matches!(r, Ok(Some(_)))));
+            bus.poll().unwrap();
+
+            let sent = take_sent(&mut bus);
+            assert_eq!(1, sent.len());
+            assert_eq!(60928, sent[0].0.pgn());
+            assert_eq!(i.address, sent[0].0.source());
+            assert_eq!(name.to_bytes().to_vec(), sent[0].1);
+            assert_eq!(i.address, bus.address());
+            assert_eq!(Some(i.state), bus.address_claim_state());
+        }
+    }
+
+    #[test]
+    fn bus_receive_with() {
+        let mut bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+        let mut map = crate::NetworkMap::<4>::new();
+        let name = Name::from(0x80a0_2200_ff00_0064);
+        let claim = Id::new(Priority::Priority6, 60928, 10, GLOBAL_ADDRESS).unwrap();
+        bus.can
+            .received
+            .push_back(CanFrame::new(claim, &name.to_bytes()));
+
+        let mut events = Vec::new();
+        let message = bus
+            .receive_with(|message| events.extend(map.process(message, 0)))
+            .unwrap();
+        assert_eq!(Some((60928, name.to_bytes().to_vec())), message);
+        assert_eq!(events, [crate::NetworkEvent::Joined { address: 10, name }]);
+    }
+
+    #[test]
+    fn bus_respond() {
+        struct TestCase<'a> {
+            destination: u8,
+            request: &'a [u8],
+            responses: &'a [(u32, u8, usize)],
+        }
+        let test_cases = [
+            // Product Information is broadcast, it is a PDU2 PGN
+            TestCase {
+                destination: 35,
+                request: &[0x14, 0xf0, 0x01],
+                responses: &[(126996, GLOBAL_ADDRESS, 134)],
+            },
+            // Both lists are sent
+            TestCase {
+                destination: GLOBAL_ADDRESS,
+                request: &[0x00, 0xee, 0x01],
+                responses: &[(126464, GLOBAL_ADDRESS, 7), (126464, GLOBAL_ADDRESS, 4)],
+            },
+            // Addressed request for an unsupported PGN is NAKed
+            TestCase {
+                destination: 35,
+                request: &[0x16, 0xf0, 0x01],
+                responses: &[(59392, GLOBAL_ADDRESS, 8)],
+            },
+            // Broadcast request is not
+            TestCase {
+                destination: GLOBAL_ADDRESS,
+                request: &[0x16, 0xf0, 0x01],
+                responses: &[],
+            },
+            // Request for another device
+            TestCase {
+                destination: 36,
+                request: &[0x14, 0xf0, 0x01],
+                responses: &[],
+            },
+        ];
+        let product = Product::new(2100, 1234, "Model", "1.0", "A", "1", 1, 1).unwrap();
+        let pgn_list = PgnList::new(&[126464, 126996], &[59904]);
+        for i in &test_cases {
+            let bus: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+            let mut bus = bus.with_responder((product, pgn_list));
+            bus.address = 35;
+            let request = Id::new(Priority::Priority6, 59904, 12, i.destination).unwrap();
+            let results = receive_all(&mut bus, &[CanFrame::new(request, i.request)]);
+            assert!(results.iter().all(|r| r.is_ok()));
+            bus.poll().unwrap();
+
+            // Fast packets are reassembled from the sent frames
+            let mut responses = Vec::new();
+            let mut receiver: Bus<_, MockRegistry> = Bus::new(MockCan::new());
+            for frame in bus.can.frames.drain(..) {
+                let id = frame.id();
+                receiver.can.received.push_back(frame);
+                if let Some((pgn, data)) = receiver.receive().unwrap() {
+                    assert_eq!(35, id.source());
+                    responses.push((pgn, id.destination(), data.len()));
+                    if pgn == 59392 {
+                        assert_eq!(data, [1, 0xff, 0xff, 0xff, 12, 0x16, 0xf0, 0x01]);
+                    }
                 }
             }
+            assert_eq!(i.responses, &responses[..]);
         }
     }
+
+    #[test]
+    fn bus_commanded_address() {
+        struct TestCase {
+            name: Name,
+            hook: Option<fn(u8, u8) -> bool>,
+            address: u8,
+        }
+        let test_cases = [
+            TestCase {
+                name: test_name(true),
+                hook: None,
+                address: 50,
+            },
+            TestCase {
+                name: test_name(true),
+                hook: Some(|old, new| old == 35 && new == 50),
+                address: 50,
+            },
+            // Vetoed
+            TestCase {
+                name: test_name(true),
+                hook: Some(|_, _| false),
+                address: 35,
+            },
+            // Commanding another device
+            TestCase {
+                name: test_name(false),
+                hook: None,
+                address: 35,
+            },
+        ];
+        fn check<H: CommandedAddressHook>(
+            i: &TestCase,
+            mut bus: Bus<MockCan, MockRegistry, MockClock, (), H>,
+            clock: MockClock,
+        ) {
+            bus.claim_address(test_name(true), 35).unwrap();
+            clock.advance(250);
+            bus.poll().unwrap();
+            take_sent(&mut bus);
+
+            // 65240 is 9 bytes long and sent as a broadcast
+            let mut data = i.name.to_bytes().to_vec();
+            data.push(50);
+            let cm = Id::new(Priority::Priority7, 60416, 12, GLOBAL_ADDRESS).unwrap();
+            let dt = Id::new(Priority::Priority7, 60160, 12, GLOBAL_ADDRESS).unwrap();
+            let mut second = [0xff; 8];
+            second[0] = 2;
+            second[1..3].copy_from_slice(&data[7..]);
+            let results = receive_all(
+                &mut bus,
+                &[
+                    CanFrame::new(cm, &[0x20, 9, 0, 2, 0xff, 0xd8, 0xfe, 0x00]),
+                    CanFrame::new(dt, &[&[1], &data[..7]].concat()),
+                    CanFrame::new(dt, &second),
+                ],
+            );
+            assert_eq!(results[2].as_ref().unwrap(), &Some((65240, data)));
+            bus.poll().unwrap();
+
+            let sent = take_sent(&mut bus);
+            assert_eq!(i.address, bus.address());
+            if i.address == 50 {
+                assert_eq!(1, sent.len());
+                assert_eq!(60928, sent[0].0.pgn());
+                assert_eq!(50, sent[0].0.source());
+                assert_eq!(Some(AddressClaimState::Claiming), bus.address_claim_state());
+            } else {
+                assert!(sent.is_empty());
+                assert_eq!(Some(AddressClaimState::Claimed), bus.address_claim_state());
+            }
+        }
+        for i in &test_cases {
+            let clock = MockClock::default();
+            let bus: Bus<_, MockRegistry, _> = Bus::with_clock(MockCan::new(), clock.clone());
+            match i.hook {
+                Some(hook) => check(i, bus.with_commanded_address_hook(hook), clock),
+                None => check(i, bus, clock),
+            }
+        }
+
+        // Hooks may keep state, e.g. to persist the new address
+        let mut persisted = None;
+        let clock = MockClock::default();
+        let bus: Bus<_, MockRegistry, _> = Bus::with_clock(MockCan::new(), clock.clone());
+        check(
+            &test_cases[0],
+            bus.with_commanded_address_hook(|_, new| {
+                persisted = Some(new);
+                true
+            }),
+            clock,
+        );
+        assert_eq!(Some(50), persisted);
+    }
 }
@@ -0,0 +1,21 @@
+/// Monotonic time source driving the protocol timeouts of the bus
+pub trait Clock {
+    /// Whether the clock advances, protocols that wait for a timeout to complete are only
+    /// available then
+    const ADVANCES: bool = true;
+
+    /// Milliseconds elapsed since an arbitrary, fixed point in time
+    fn now(&self) -> u64;
+}
+
+/// Clock that never advances, so timeouts never expire
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoClock;
+
+impl Clock for NoClock {
+    const ADVANCES: bool = false;
+
+    fn now(&self) -> u64 {
+        0
+    }
+}
@@ -0,0 +1,140 @@
+use core::convert::TryFrom;
+
+use crate::Responder;
+
+pub(crate) const PGN_CONFIGURATION_INFORMATION: u32 = 126998;
+
+// STRING_LAU control byte of ASCII strings
+const ASCII: u8 = 1;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConfigurationError {
+    InvalidLength,
+    InvalidString,
+    UnsupportedEncoding,
+}
+
+pub type Result<T> = core::result::Result<T, ConfigurationError>;
+
+/// 126998 Configuration Information
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Configuration<'a> {
+    installation_description1: &'a str,
+    installation_description2: &'a str,
+    manufacturer_information: &'a str,
+}
+
+impl<'a> Configuration<'a> {
+    /// Decodes a 126998 Configuration Information payload, the strings borrow from `data`.
+    /// Only ASCII strings are supported.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self> {
+        let (installation_description1, data) = string_lau(data)?;
+        let (installation_description2, data) = string_lau(data)?;
+        let (manufacturer_information, _) = string_lau(data)?;
+        Ok(Configuration {
+            installation_description1,
+            installation_description2,
+            manufacturer_information,
+        })
+    }
+
+    pub fn installation_description1(&self) -> &'a str {
+        self.installation_description1
+    }
+
+    pub fn installation_description2(&self) -> &'a str {
+        self.installation_description2
+    }
+
+    pub fn manufacturer_information(&self) -> &'a str {
+        self.manufacturer_information
+    }
+}
+
+impl<'a> Responder for Configuration<'a> {
+    fn respond(&self, pgn: u32, index: usize, buf: &mut [u8]) -> Option<usize> {
+        if pgn != PGN_CONFIGURATION_INFORMATION || index > 0 {
+            return None;
+        }
+
+        let mut length = 0;
+        for string in [
+            self.installation_description1,
+            self.installation_description2,
+            self.manufacturer_information,
+        ] {
+            let field = buf.get_mut(length..length + 2 + string.len())?;
+            field[0] = u8::try_from(2 + string.len()).ok()?;
+            field[1] = ASCII;
+            field[2..].copy_from_slice(string.as_bytes());
+            length += field.len();
+        }
+        Some(length)
+    }
+}
+
+/// Splits a string prefixed with its length, including the length and control bytes, and
+/// its encoding off `data`
+fn string_lau(data: &[u8]) -> Result<(&str, &[u8])> {
+    if data.len() < 2 || (data[0] as usize) < 2 || data[0] as usize > data.len() {
+        return Err(ConfigurationError::InvalidLength);
+    }
+    if data[1] != ASCII {
+        return Err(ConfigurationError::UnsupportedEncoding);
+    }
+
+    let (string, rest) = data.split_at(data[0] as usize);
+    let string =
+        core::str::from_utf8(&string[2..]).map_err(|_| ConfigurationError::InvalidString)?;
+    Ok((string, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::configuration::ConfigurationError;
+    use crate::{Configuration, Responder};
+
+    #[test]
+    fn configuration_from_bytes() {
+        struct TestCase<'a> {
+            data: &'a [u8],
+            result: Result<Configuration<'a>, ConfigurationError>,
+        }
+        let test_cases = [
+            TestCase {
+                data: &[6, 1, b'A', b'f', b't', b'2', 2, 1, 4, 1, b'a', b'b'],
+                result: Ok(Configuration {
+                    installation_description1: "Aft2",
+                    installation_description2: "",
+                    manufacturer_information: "ab",
+                }),
+            },
+            TestCase {
+                data: &[6, 1, b'A', b'f', b't', b'2', 2, 1],
+                result: Err(ConfigurationError::InvalidLength),
+            },
+            TestCase {
+                data: &[6, 0, b'A', 0, b'f', 0, 2, 1, 2, 1],
+                result: Err(ConfigurationError::UnsupportedEncoding),
+            },
+            TestCase {
+                data: &[3, 1, 0xc3, 2, 1, 2, 1],
+                result: Err(ConfigurationError::InvalidString),
+            },
+        ];
+        for i in &test_cases {
+            assert_eq!(i.result, Configuration::from_bytes(i.data));
+
+            // Encoding the decoded configuration gives the payload back
+            if let Ok(configuration) = i.result {
+                let mut buf = [0; 32];
+                let length = configuration.respond(126998, 0, &mut buf).unwrap();
+                assert_eq!(i.data, &buf[..length]);
+                assert_eq!(
+                    None,
+                    configuration.respond(126998, 0, &mut buf[..length - 1])
+                );
+            }
+        }
+    }
+}
@@ -1,3 +1,6 @@
+/// Maximum payload of a fast packet: 6 bytes in the first frame, 7 bytes in each of the 31 following frames
+pub const MAX_FAST_PACKET_SIZE: usize = 223;
+
 #[derive(Debug, Copy, Clone)]
 pub enum FastPacketError {
     UnexpectedFrameIndex { index: usize, expected: usize },
@@ -34,10 +37,16 @@ impl FastPacketCache {
     }
 
     pub fn extend(&mut self, frame_index: usize, data: &[u8]) -> Result<bool, FastPacketError> {
-        if frame_index != self.last_frame + 1 {
+        // Frame 0 opens the packet, every following frame increments the index
+        let expected = if self.data.is_empty() {
+            0
+        } else {
+            self.last_frame + 1
+        };
+        if frame_index != expected {
             return Err(FastPacketError::UnexpectedFrameIndex {
                 index: frame_index,
-                expected: self.last_frame + 1,
+                expected,
             });
         }
 
@@ -50,8 +59,115 @@ impl FastPacketCache {
         // }
 
         self.data.extend_from_slice(data).unwrap();
-        self.last_frame += 1;
+        self.last_frame = frame_index;
 
         Ok(self.is_complete())
     }
 }
+
+/// Splits a payload into the 8 byte frames of a fast packet
+pub struct FastPacketFrames<'a> {
+    data: &'a [u8],
+    sequence: u8,
+    index: u8,
+    offset: usize,
+}
+
+impl<'a> FastPacketFrames<'a> {
+    pub fn new(sequence: u8, data: &'a [u8]) -> Result<Self, FastPacketError> {
+        if data.len() > MAX_FAST_PACKET_SIZE {
+            return Err(FastPacketError::PacketTooBig {
+                actual: data.len(),
+                expected: MAX_FAST_PACKET_SIZE,
+            });
+        }
+
+        Ok(Self {
+            data,
+            sequence: sequence & 0x07,
+            index: 0,
+            offset: 0,
+        })
+    }
+}
+
+impl<'a> Iterator for FastPacketFrames<'a> {
+    type Item = [u8; 8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The first frame is always sent, even for an empty payload
+        if self.index > 0 && self.offset >= self.data.len() {
+            return None;
+        }
+
+        // Unused bytes of the last frame are padded with 0xff
+        let mut frame = [0xff; 8];
+        frame[0] = (self.sequence << 5) | self.index;
+        let start = if self.index == 0 {
+            // First frame carries the total size of the payload
+            frame[1] = self.data.len() as u8;
+            2
+        } else {
+            1
+        };
+
+        let len = (8 - start).min(self.data.len() - self.offset);
+        frame[start..start + len].copy_from_slice(&self.data[self.offset..self.offset + len]);
+        self.offset += len;
+        self.index += 1;
+
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_packet_frames() {
+        let data: [u8; 20] = [
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20,
+        ];
+        let mut frames = FastPacketFrames::new(5, &data).unwrap();
+
+        assert_eq!(frames.next(), Some([0xa0, 20, 1, 2, 3, 4, 5, 6]));
+        assert_eq!(frames.next(), Some([0xa1, 7, 8, 9, 10, 11, 12, 13]));
+        assert_eq!(frames.next(), Some([0xa2, 14, 15, 16, 17, 18, 19, 20]));
+        assert_eq!(frames.next(), None);
+    }
+
+    #[test]
+    fn fast_packet_frames_padding() {
+        let data = [1, 2, 3];
+        let frames: heapless::Vec<[u8; 8], 4> = FastPacketFrames::new(7, &data).unwrap().collect();
+
+        assert_eq!(&frames[..], &[[0xe0, 3, 1, 2, 3, 0xff, 0xff, 0xff]]);
+    }
+
+    #[test]
+    fn fast_packet_frames_roundtrip() {
+        let data: heapless::Vec<u8, MAX_FAST_PACKET_SIZE> =
+            (0..MAX_FAST_PACKET_SIZE as u8).collect();
+        let mut frames = FastPacketFrames::new(1, &data).unwrap();
+
+        let first = frames.next().unwrap();
+        let mut cache = FastPacketCache::new(first[1] as usize);
+        cache.extend(0, &first[2..]).unwrap();
+        for frame in frames {
+            cache
+                .extend((frame[0] & 0x1f) as usize, &frame[1..])
+                .unwrap();
+        }
+
+        assert_eq!(cache.last_frame, 31);
+        assert_eq!(cache.complete_data(), Some(&data[..]));
+    }
+
+    #[test]
+    fn fast_packet_frames_too_big() {
+        let data = [0; MAX_FAST_PACKET_SIZE + 1];
+
+        assert!(FastPacketFrames::new(0, &data).is_err());
+    }
+}
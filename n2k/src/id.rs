@@ -71,13 +71,11 @@ impl Id {
         let dp: u8 = ((self.0 >> 24) & 1) as u8;
         if pf <= 239 {
             // PDU1 format, the PS contains the destination address
-            let pgn = ((dp as u32) << 16) + ((pf as u32) << 8);
-            pgn as u32
+            ((dp as u32) << 16) + ((pf as u32) << 8)
         } else {
             // PDU2 format, the PGN is extended
             let ps: u8 = (self.0 >> 8) as u8;
-            let pgn = ((dp as u32) << 16) + ((pf as u32) << 8) + (ps as u32);
-            pgn as u32
+            ((dp as u32) << 16) + ((pf as u32) << 8) + (ps as u32)
         }
     }
 
@@ -185,7 +183,7 @@ mod tests {
             let id: u32 = Id::new(i.prio, i.pgn, i.src, i.dst)
                 .expect("Invalid parameter")
                 .value();
-            assert_eq!(id, i.0)
+            assert_eq!(id, i.id)
         }
     }
 
@@ -210,7 +208,7 @@ mod tests {
             },
         ];
         for i in &test_cases {
-            let id = Id::try_from(i.0).expect("Invalid CanID");
+            let id = Id::try_from(i.id).expect("Invalid CanID");
             assert_eq!(id.priority(), i.prio)
         }
     }
@@ -236,7 +234,7 @@ mod tests {
             },
         ];
         for i in &test_cases {
-            let id = Id::try_from(i.0).expect("Invalid CanID");
+            let id = Id::try_from(i.id).expect("Invalid CanID");
             assert_eq!(id.pgn(), i.pgn)
         }
     }
@@ -262,7 +260,7 @@ mod tests {
             },
         ];
         for i in &test_cases {
-            let id = Id::try_from(i.0).expect("Invalid CanID");
+            let id = Id::try_from(i.id).expect("Invalid CanID");
             assert_eq!(id.source(), i.src)
         }
     }
@@ -288,7 +286,7 @@ mod tests {
             },
         ];
         for i in &test_cases {
-            let id = Id::try_from(i.0).expect("Invalid CanID");
+            let id = Id::try_from(i.id).expect("Invalid CanID");
             assert_eq!(id.destination(), i.dst)
         }
     }
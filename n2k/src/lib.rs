@@ -4,26 +4,49 @@ pub const GLOBAL_ADDRESS: u8 = 0xff;
 
 use embedded_hal_can as hal_can;
 
+mod address_claim;
+pub use address_claim::{
+    AddressClaimState, CommandedAddressHook, MAX_ADDRESS, MIN_DYNAMIC_ADDRESS, NULL_ADDRESS,
+};
+
 mod bus;
 pub use bus::{Bus, BusError};
 
+mod clock;
+pub use clock::{Clock, NoClock};
+
 mod id;
 pub use id::{Id, IdError, Priority};
 
 mod message;
-pub use message::Message;
+pub use message::{Message, MessageError};
 
 mod name;
-pub use name::Name;
+pub use name::{DeviceClass, DeviceFunction, IndustryGroup, Name, NameBuilder, NameError};
 
 mod product;
-pub use product::Product;
+pub use product::{
+    Product, ProductError, MAX_CERTIFICATION_LEVEL, MAX_LOAD_EQUIVALENCY, PGN_PRODUCT_INFORMATION,
+    PRODUCT_INFORMATION_SIZE, PRODUCT_STRING_SIZE,
+};
+
+mod configuration;
+pub use configuration::{Configuration, ConfigurationError};
+
+mod network;
+pub use network::{Device, NetworkEvent, NetworkMap};
 
 mod frame;
 pub use frame::CanFrame;
 
 mod fast_packet;
 
+mod request;
+pub use request::{AcknowledgementControl, PgnList, Responder};
+
+mod transport;
+pub use transport::{AbortReason, TransportError};
+
 pub trait PgnRegistry {
     type Message;
     type Error;
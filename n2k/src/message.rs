@@ -1,8 +1,8 @@
-use crate::Id;
+use crate::{transport::MAX_TRANSPORT_SIZE, Id};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MessageError {
-    Max255Bytes,
+    Max1785Bytes,
 }
 
 pub type Result<T> = core::result::Result<T, MessageError>;
@@ -14,8 +14,8 @@ pub struct Message<'a> {
 
 impl<'a> Message<'a> {
     pub fn new(id: Id, data: &'a [u8]) -> Result<Self> {
-        if data.len() > 255 {
-            return Err(MessageError::Max255Bytes);
+        if data.len() > MAX_TRANSPORT_SIZE {
+            return Err(MessageError::Max1785Bytes);
         }
 
         Ok(Message { id, data })
@@ -26,6 +26,6 @@ impl<'a> Message<'a> {
     }
 
     pub fn data(&self) -> &[u8] {
-        &self.data
+        self.data
     }
 }
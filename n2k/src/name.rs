@@ -1,8 +1,22 @@
+use core::convert::TryFrom;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NameError {
+    InvalidLength,
+    OutOfRange,
+}
+
+pub type Result<T> = core::result::Result<T, NameError>;
+
+/// ISO 11783 NAME of a device. NAMEs are ordered by their value, the lower NAME wins address
+/// claim arbitration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Name {
     name: u64,
 }
 
 impl Name {
+    /// See `Name::builder` for a typed alternative
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         arbitrary_address_capable: bool,
@@ -101,10 +115,572 @@ impl Name {
     pub fn value(&self) -> u64 {
         self.name
     }
+
+    /// Typed vehicle system
+    pub fn device_class(&self) -> DeviceClass {
+        DeviceClass::from(self.vehicle_system())
+    }
+
+    /// Typed function, its meaning depends on the device class
+    pub fn device_function(&self) -> DeviceFunction {
+        DeviceFunction::new(self.device_class(), self.function())
+    }
+
+    /// 60928 Address Claim payload
+    pub fn to_bytes(&self) -> [u8; 8] {
+        self.name.to_le_bytes()
+    }
+
+    pub fn from_bytes(bytes: [u8; 8]) -> Self {
+        Name {
+            name: u64::from_le_bytes(bytes),
+        }
+    }
+
+    pub fn builder() -> NameBuilder {
+        NameBuilder::default()
+    }
+}
+
+impl From<u64> for Name {
+    fn from(name: u64) -> Self {
+        Name { name }
+    }
+}
+
+impl From<Name> for u64 {
+    fn from(name: Name) -> Self {
+        name.name
+    }
+}
+
+impl TryFrom<&[u8]> for Name {
+    type Error = NameError;
+
+    /// Decodes a 60928 Address Claim payload
+    fn try_from(data: &[u8]) -> Result<Self> {
+        let bytes = <[u8; 8]>::try_from(data).map_err(|_| NameError::InvalidLength)?;
+        Ok(Name::from_bytes(bytes))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IndustryGroup {
+    Global,
+    Highway,
+    Agriculture,
+    Construction,
+    Marine,
+    Industrial,
+    Other(u8),
+}
+
+impl From<u8> for IndustryGroup {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => IndustryGroup::Global,
+            1 => IndustryGroup::Highway,
+            2 => IndustryGroup::Agriculture,
+            3 => IndustryGroup::Construction,
+            4 => IndustryGroup::Marine,
+            5 => IndustryGroup::Industrial,
+            value => IndustryGroup::Other(value),
+        }
+    }
+}
+
+impl From<IndustryGroup> for u8 {
+    fn from(group: IndustryGroup) -> Self {
+        match group {
+            IndustryGroup::Global => 0,
+            IndustryGroup::Highway => 1,
+            IndustryGroup::Agriculture => 2,
+            IndustryGroup::Construction => 3,
+            IndustryGroup::Marine => 4,
+            IndustryGroup::Industrial => 5,
+            IndustryGroup::Other(value) => value,
+        }
+    }
+}
+
+/// NMEA 2000 device class, the vehicle system of the NAME
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DeviceClass {
+    SystemTools,
+    SafetySystems,
+    InternetworkDevice,
+    ElectricalDistribution,
+    ElectricalGeneration,
+    SteeringAndControlSurfaces,
+    Propulsion,
+    Navigation,
+    Communication,
+    SensorCommunicationInterface,
+    Instrumentation,
+    ExternalEnvironment,
+    InternalEnvironment,
+    DeckCargoFishing,
+    Display,
+    Entertainment,
+    Other(u8),
+}
+
+const DEVICE_CLASSES: [(DeviceClass, u8); 16] = [
+    (DeviceClass::SystemTools, 10),
+    (DeviceClass::SafetySystems, 20),
+    (DeviceClass::InternetworkDevice, 25),
+    (DeviceClass::ElectricalDistribution, 30),
+    (DeviceClass::ElectricalGeneration, 35),
+    (DeviceClass::SteeringAndControlSurfaces, 40),
+    (DeviceClass::Propulsion, 50),
+    (DeviceClass::Navigation, 60),
+    (DeviceClass::Communication, 70),
+    (DeviceClass::SensorCommunicationInterface, 75),
+    (DeviceClass::Instrumentation, 80),
+    (DeviceClass::ExternalEnvironment, 85),
+    (DeviceClass::InternalEnvironment, 90),
+    (DeviceClass::DeckCargoFishing, 100),
+    (DeviceClass::Display, 120),
+    (DeviceClass::Entertainment, 125),
+];
+
+impl From<u8> for DeviceClass {
+    fn from(value: u8) -> Self {
+        DEVICE_CLASSES
+            .iter()
+            .find(|(_, class_value)| *class_value == value)
+            .map_or(DeviceClass::Other(value), |(class, _)| *class)
+    }
+}
+
+impl From<DeviceClass> for u8 {
+    fn from(class: DeviceClass) -> Self {
+        match class {
+            DeviceClass::Other(value) => value,
+            class => DEVICE_CLASSES
+                .iter()
+                .find(|(known, _)| *known == class)
+                .map(|(_, value)| *value)
+                .unwrap(),
+        }
+    }
+}
+
+/// NMEA 2000 device function, the function of the NAME. Function codes are defined per
+/// device class.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DeviceFunction {
+    Diagnostic,
+    BusTrafficLogger,
+    AlarmEnunciator,
+    ManOverboard,
+    PcGateway,
+    Nmea0183Gateway,
+    Router,
+    Bridge,
+    Repeater,
+    BinaryEventMonitor,
+    LoadController,
+    Battery,
+    PowerConverter,
+    FollowUpController,
+    ModeController,
+    Autopilot,
+    Rudder,
+    HeadingSensor,
+    Trim,
+    Attitude,
+    EngineRoomMonitoring,
+    Engine,
+    EngineController,
+    EngineGateway,
+    Transmission,
+    ThrottleShiftControl,
+    BottomDepth,
+    BottomDepthSpeed,
+    BottomDepthSpeedTemperature,
+    OwnshipAttitude,
+    OwnshipPositionGnss,
+    Speed,
+    TurnRateIndicator,
+    IntegratedNavigation,
+    Ais,
+    Radar,
+    Dsc,
+    Radiotelephone,
+    Temperature,
+    Pressure,
+    FluidLevel,
+    Flow,
+    Humidity,
+    TimeDate,
+    IntegratedInstrumentation,
+    GeneralPurposeDisplay,
+    WeatherInstruments,
+    Atmospheric,
+    Aquatic,
+    Hvac,
+    Display,
+    MultimediaPlayer,
+    MultimediaController,
+    Other(DeviceClass, u8),
+}
+
+const DEVICE_FUNCTIONS: [(DeviceFunction, DeviceClass, u8); 53] = [
+    (DeviceFunction::Diagnostic, DeviceClass::SystemTools, 130),
+    (
+        DeviceFunction::BusTrafficLogger,
+        DeviceClass::SystemTools,
+        140,
+    ),
+    (
+        DeviceFunction::AlarmEnunciator,
+        DeviceClass::SafetySystems,
+        110,
+    ),
+    (
+        DeviceFunction::ManOverboard,
+        DeviceClass::SafetySystems,
+        135,
+    ),
+    (
+        DeviceFunction::PcGateway,
+        DeviceClass::InternetworkDevice,
+        130,
+    ),
+    (
+        DeviceFunction::Nmea0183Gateway,
+        DeviceClass::InternetworkDevice,
+        135,
+    ),
+    (DeviceFunction::Router, DeviceClass::InternetworkDevice, 140),
+    (DeviceFunction::Bridge, DeviceClass::InternetworkDevice, 150),
+    (
+        DeviceFunction::Repeater,
+        DeviceClass::InternetworkDevice,
+        160,
+    ),
+    (
+        DeviceFunction::BinaryEventMonitor,
+        DeviceClass::ElectricalDistribution,
+        130,
+    ),
+    (
+        DeviceFunction::LoadController,
+        DeviceClass::ElectricalDistribution,
+        140,
+    ),
+    (
+        DeviceFunction::PowerConverter,
+        DeviceClass::ElectricalGeneration,
+        160,
+    ),
+    (
+        DeviceFunction::Battery,
+        DeviceClass::ElectricalGeneration,
+        170,
+    ),
+    (
+        DeviceFunction::FollowUpController,
+        DeviceClass::SteeringAndControlSurfaces,
+        130,
+    ),
+    (
+        DeviceFunction::ModeController,
+        DeviceClass::SteeringAndControlSurfaces,
+        140,
+    ),
+    (
+        DeviceFunction::Autopilot,
+        DeviceClass::SteeringAndControlSurfaces,
+        150,
+    ),
+    (
+        DeviceFunction::Rudder,
+        DeviceClass::SteeringAndControlSurfaces,
+        155,
+    ),
+    (
+        DeviceFunction::HeadingSensor,
+        DeviceClass::SteeringAndControlSurfaces,
+        160,
+    ),
+    (
+        DeviceFunction::Trim,
+        DeviceClass::SteeringAndControlSurfaces,
+        170,
+    ),
+    (
+        DeviceFunction::Attitude,
+        DeviceClass::SteeringAndControlSurfaces,
+        180,
+    ),
+    (
+        DeviceFunction::EngineRoomMonitoring,
+        DeviceClass::Propulsion,
+        130,
+    ),
+    (DeviceFunction::Engine, DeviceClass::Propulsion, 140),
+    (
+        DeviceFunction::EngineController,
+        DeviceClass::Propulsion,
+        150,
+    ),
+    (DeviceFunction::EngineGateway, DeviceClass::Propulsion, 160),
+    (DeviceFunction::Transmission, DeviceClass::Propulsion, 165),
+    (
+        DeviceFunction::ThrottleShiftControl,
+        DeviceClass::Propulsion,
+        170,
+    ),
+    (DeviceFunction::BottomDepth, DeviceClass::Navigation, 130),
+    (
+        DeviceFunction::BottomDepthSpeed,
+        DeviceClass::Navigation,
+        135,
+    ),
+    (
+        DeviceFunction::BottomDepthSpeedTemperature,
+        DeviceClass::Navigation,
+        136,
+    ),
+    (
+        DeviceFunction::OwnshipAttitude,
+        DeviceClass::Navigation,
+        140,
+    ),
+    (
+        DeviceFunction::OwnshipPositionGnss,
+        DeviceClass::Navigation,
+        145,
+    ),
+    (DeviceFunction::Speed, DeviceClass::Navigation, 155),
+    (
+        DeviceFunction::TurnRateIndicator,
+        DeviceClass::Navigation,
+        160,
+    ),
+    (
+        DeviceFunction::IntegratedNavigation,
+        DeviceClass::Navigation,
+        170,
+    ),
+    (DeviceFunction::Ais, DeviceClass::Navigation, 195),
+    (DeviceFunction::Radar, DeviceClass::Navigation, 200),
+    (DeviceFunction::Dsc, DeviceClass::Communication, 150),
+    (
+        DeviceFunction::Radiotelephone,
+        DeviceClass::Communication,
+        190,
+    ),
+    (
+        DeviceFunction::Temperature,
+        DeviceClass::SensorCommunicationInterface,
+        130,
+    ),
+    (
+        DeviceFunction::Pressure,
+        DeviceClass::SensorCommunicationInterface,
+        140,
+    ),
+    (
+        DeviceFunction::FluidLevel,
+        DeviceClass::SensorCommunicationInterface,
+        150,
+    ),
+    (
+        DeviceFunction::Flow,
+        DeviceClass::SensorCommunicationInterface,
+        160,
+    ),
+    (
+        DeviceFunction::Humidity,
+        DeviceClass::SensorCommunicationInterface,
+        170,
+    ),
+    (DeviceFunction::TimeDate, DeviceClass::Instrumentation, 130),
+    (
+        DeviceFunction::IntegratedInstrumentation,
+        DeviceClass::Instrumentation,
+        150,
+    ),
+    (
+        DeviceFunction::GeneralPurposeDisplay,
+        DeviceClass::Instrumentation,
+        160,
+    ),
+    (
+        DeviceFunction::WeatherInstruments,
+        DeviceClass::Instrumentation,
+        180,
+    ),
+    (
+        DeviceFunction::Atmospheric,
+        DeviceClass::ExternalEnvironment,
+        130,
+    ),
+    (
+        DeviceFunction::Aquatic,
+        DeviceClass::ExternalEnvironment,
+        160,
+    ),
+    (DeviceFunction::Hvac, DeviceClass::InternalEnvironment, 130),
+    (DeviceFunction::Display, DeviceClass::Display, 130),
+    (
+        DeviceFunction::MultimediaPlayer,
+        DeviceClass::Entertainment,
+        130,
+    ),
+    (
+        DeviceFunction::MultimediaController,
+        DeviceClass::Entertainment,
+        140,
+    ),
+];
+
+impl DeviceFunction {
+    /// Function `value` of a device of `class`
+    pub fn new(class: DeviceClass, value: u8) -> Self {
+        DEVICE_FUNCTIONS
+            .iter()
+            .find(|(_, function_class, function_value)| {
+                *function_class == class && *function_value == value
+            })
+            .map_or(DeviceFunction::Other(class, value), |(function, _, _)| {
+                *function
+            })
+    }
+
+    /// Device class the function is defined for
+    pub fn device_class(&self) -> DeviceClass {
+        self.lookup().0
+    }
+
+    pub fn value(&self) -> u8 {
+        self.lookup().1
+    }
+
+    fn lookup(&self) -> (DeviceClass, u8) {
+        match self {
+            DeviceFunction::Other(class, value) => (*class, *value),
+            function => DEVICE_FUNCTIONS
+                .iter()
+                .find(|(known, _, _)| known == function)
+                .map(|(_, class, value)| (*class, *value))
+                .unwrap(),
+        }
+    }
+}
+
+/// Typed construction of a `Name`, fields not set are 0 except for the marine industry group
+#[derive(Copy, Clone, Debug)]
+pub struct NameBuilder {
+    arbitrary_address_capable: bool,
+    industry_group: IndustryGroup,
+    system_instance: u8,
+    device_function: DeviceFunction,
+    function_instance: u8,
+    ecu_instance: u8,
+    manufacturer_code: u16,
+    identity_number: u32,
+}
+
+impl Default for NameBuilder {
+    fn default() -> Self {
+        NameBuilder {
+            arbitrary_address_capable: false,
+            industry_group: IndustryGroup::Marine,
+            system_instance: 0,
+            device_function: DeviceFunction::Other(DeviceClass::Other(0), 0),
+            function_instance: 0,
+            ecu_instance: 0,
+            manufacturer_code: 0,
+            identity_number: 0,
+        }
+    }
+}
+
+impl NameBuilder {
+    pub fn arbitrary_address_capable(mut self, arbitrary_address_capable: bool) -> Self {
+        self.arbitrary_address_capable = arbitrary_address_capable;
+        self
+    }
+
+    pub fn industry_group(mut self, industry_group: IndustryGroup) -> Self {
+        self.industry_group = industry_group;
+        self
+    }
+
+    /// Vehicle system instance - 4 bits
+    pub fn system_instance(mut self, system_instance: u8) -> Self {
+        self.system_instance = system_instance;
+        self
+    }
+
+    /// Sets the function and the device class it belongs to
+    pub fn device_function(mut self, device_function: DeviceFunction) -> Self {
+        self.device_function = device_function;
+        self
+    }
+
+    /// Function instance - 5 bits
+    pub fn function_instance(mut self, function_instance: u8) -> Self {
+        self.function_instance = function_instance;
+        self
+    }
+
+    /// ECU instance - 3 bits
+    pub fn ecu_instance(mut self, ecu_instance: u8) -> Self {
+        self.ecu_instance = ecu_instance;
+        self
+    }
+
+    /// Manufacturer code - 11 bits
+    pub fn manufacturer_code(mut self, manufacturer_code: u16) -> Self {
+        self.manufacturer_code = manufacturer_code;
+        self
+    }
+
+    /// Identity number - 21 bits
+    pub fn identity_number(mut self, identity_number: u32) -> Self {
+        self.identity_number = identity_number;
+        self
+    }
+
+    /// Fails with `NameError::OutOfRange` if a value does not fit its field
+    pub fn build(self) -> Result<Name> {
+        let industry_group = u8::from(self.industry_group);
+        let device_class = u8::from(self.device_function.device_class());
+        if industry_group > 0x07
+            || self.system_instance > 0x0f
+            || device_class > 0x7f
+            || self.function_instance > 0x1f
+            || self.ecu_instance > 0x07
+            || self.manufacturer_code > 0x07ff
+            || self.identity_number > 0x1fffff
+        {
+            return Err(NameError::OutOfRange);
+        }
+
+        Ok(Name::new(
+            self.arbitrary_address_capable,
+            industry_group,
+            self.system_instance,
+            device_class,
+            self.device_function.value(),
+            self.function_instance,
+            self.ecu_instance,
+            self.manufacturer_code,
+            self.identity_number,
+        ))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use core::convert::TryFrom;
+
+    use crate::name::{DeviceClass, DeviceFunction, IndustryGroup, NameError};
     use crate::Name;
 
     #[test]
@@ -182,4 +758,72 @@ mod tests {
             assert_eq!(i.identity_number, name.identity_number());
         }
     }
+
+    #[test]
+    fn name_bytes() {
+        let name = Name::from(0xc050_8a00_ff00_0064);
+        let bytes = [0x64, 0x00, 0x00, 0xff, 0x00, 0x8a, 0x50, 0xc0];
+        assert_eq!(bytes, name.to_bytes());
+        assert_eq!(name, Name::from_bytes(bytes));
+        assert_eq!(Ok(name), Name::try_from(&bytes[..]));
+        assert_eq!(Err(NameError::InvalidLength), Name::try_from(&bytes[1..]));
+        assert_eq!(0xc050_8a00_ff00_0064, u64::from(name));
+    }
+
+    #[test]
+    fn name_ord() {
+        // The lower NAME wins, a self-configurable NAME loses against any other
+        let fixed = Name::new(false, 4, 0, 60, 145, 0, 0, 2040, 2);
+        let arbitrary = Name::new(true, 4, 0, 60, 145, 0, 0, 2040, 1);
+        let lower_identity = Name::new(false, 4, 0, 60, 145, 0, 0, 2040, 1);
+        assert!(fixed < arbitrary);
+        assert!(lower_identity < fixed);
+        assert_eq!(
+            Some(&lower_identity),
+            [arbitrary, fixed, lower_identity].iter().min()
+        );
+    }
+
+    #[test]
+    fn name_builder() {
+        let name = Name::builder()
+            .arbitrary_address_capable(true)
+            .device_function(DeviceFunction::Autopilot)
+            .system_instance(1)
+            .function_instance(2)
+            .ecu_instance(3)
+            .manufacturer_code(2040)
+            .identity_number(0x1fffff)
+            .build()
+            .unwrap();
+        assert_eq!(name, Name::new(true, 4, 1, 40, 150, 2, 3, 2040, 0x1fffff));
+        assert_eq!(DeviceClass::SteeringAndControlSurfaces, name.device_class());
+        assert_eq!(DeviceFunction::Autopilot, name.device_function());
+        assert_eq!(
+            IndustryGroup::Marine,
+            IndustryGroup::from(name.industry_group())
+        );
+
+        let name = Name::builder()
+            .industry_group(IndustryGroup::Global)
+            .device_function(DeviceFunction::Other(DeviceClass::Navigation, 250))
+            .build()
+            .unwrap();
+        assert_eq!(DeviceClass::Navigation, name.device_class());
+        assert_eq!(
+            DeviceFunction::Other(DeviceClass::Navigation, 250),
+            name.device_function()
+        );
+
+        assert_eq!(
+            Err(NameError::OutOfRange),
+            Name::builder().identity_number(0x200000).build()
+        );
+        assert_eq!(
+            Err(NameError::OutOfRange),
+            Name::builder()
+                .industry_group(IndustryGroup::Other(8))
+                .build()
+        );
+    }
 }
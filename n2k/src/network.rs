@@ -0,0 +1,402 @@
+use core::convert::TryFrom;
+
+use heapless::{FnvIndexMap, Vec};
+
+use crate::{
+    address_claim::{MAX_ADDRESS, PGN_ISO_ADDRESS_CLAIM},
+    configuration::PGN_CONFIGURATION_INFORMATION,
+    fast_packet::MAX_FAST_PACKET_SIZE,
+    product::PGN_PRODUCT_INFORMATION,
+    Configuration, Message, Name, Product, PRODUCT_INFORMATION_SIZE,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NetworkEvent {
+    /// A device claimed an address for the first time
+    Joined { address: u8, name: Name },
+    /// A known device claimed another address
+    AddressChanged { from: u8, to: u8, name: Name },
+    /// A device could not claim an address or went silent
+    Left { address: u8, name: Option<Name> },
+}
+
+/// Device seen on the bus
+pub struct Device {
+    name: Option<Name>,
+    // raw payloads, decoded on access
+    product: Vec<u8, PRODUCT_INFORMATION_SIZE>,
+    configuration: Vec<u8, MAX_FAST_PACKET_SIZE>,
+    last_seen: u64,
+}
+
+impl Device {
+    fn new(name: Option<Name>, now: u64) -> Self {
+        Device {
+            name,
+            product: Vec::new(),
+            configuration: Vec::new(),
+            last_seen: now,
+        }
+    }
+
+    /// NAME of the device, None if it has not claimed its address since we started listening
+    pub fn name(&self) -> Option<Name> {
+        self.name
+    }
+
+    /// Last received 126996 Product Information
+    pub fn product(&self) -> Option<Product<'_>> {
+        Product::from_bytes(&self.product).ok()
+    }
+
+    /// Last received 126998 Configuration Information
+    pub fn configuration(&self) -> Option<Configuration<'_>> {
+        Configuration::from_bytes(&self.configuration).ok()
+    }
+
+    /// Time of the last message received from the device, in the time base passed to
+    /// `NetworkMap::process`
+    pub fn last_seen(&self) -> u64 {
+        self.last_seen
+    }
+}
+
+/// Table of the devices on the bus by source address, built from received messages.
+/// `N` is the maximum number of devices and must be a power of two.
+pub struct NetworkMap<const N: usize> {
+    devices: FnvIndexMap<u8, Device, N>,
+}
+
+impl<const N: usize> Default for NetworkMap<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> NetworkMap<N> {
+    pub fn new() -> Self {
+        NetworkMap {
+            devices: FnvIndexMap::new(),
+        }
+    }
+
+    /// Updates the table with a received message, `now` is the current time in ms. Devices
+    /// are dropped when the table is full. Returns the events in the order they happened, a
+    /// claim of an address held by another device also reports that device as left.
+    pub fn process(&mut self, message: &Message, now: u64) -> Vec<NetworkEvent, 2> {
+        let source = message.id().source();
+        let data = message.data();
+        if message.id().pgn() == PGN_ISO_ADDRESS_CLAIM {
+            let mut events = Vec::new();
+            self.process_address_claim(source, data, now, &mut events);
+            return events;
+        }
+        if source <= MAX_ADDRESS {
+            self.process_data(message, now);
+        }
+        Vec::new()
+    }
+
+    fn process_data(&mut self, message: &Message, now: u64) -> Option<()> {
+        let source = message.id().source();
+        let data = message.data();
+        if !self.devices.contains_key(&source) {
+            self.devices.insert(source, Device::new(None, now)).ok()?;
+        }
+        let device = self.devices.get_mut(&source)?;
+        device.last_seen = now;
+        match message.id().pgn() {
+            PGN_PRODUCT_INFORMATION => {
+                device.product.clear();
+                device
+                    .product
+                    .extend_from_slice(&data[..data.len().min(PRODUCT_INFORMATION_SIZE)])
+                    .ok();
+            }
+            PGN_CONFIGURATION_INFORMATION => {
+                device.configuration.clear();
+                device
+                    .configuration
+                    .extend_from_slice(&data[..data.len().min(MAX_FAST_PACKET_SIZE)])
+                    .ok();
+            }
+            _ => {}
+        }
+        Some(())
+    }
+
+    fn process_address_claim(
+        &mut self,
+        source: u8,
+        data: &[u8],
+        now: u64,
+        events: &mut Vec<NetworkEvent, 2>,
+    ) -> Option<()> {
+        let name = Name::try_from(data).ok()?;
+        let previous = self.address_of(name);
+
+        // Claims from the null address report that the device could not claim one
+        if source > MAX_ADDRESS {
+            let address = previous?;
+            self.devices.remove(&address);
+            return events
+                .push(NetworkEvent::Left {
+                    address,
+                    name: Some(name),
+                })
+                .ok();
+        }
+        if previous == Some(source) {
+            self.devices.get_mut(&source)?.last_seen = now;
+            return Some(());
+        }
+
+        // The lower NAME keeps a contested address, a claimant with a higher one has to claim
+        // another address and the table stays as it is
+        let holder = self.devices.get(&source).and_then(|device| device.name);
+        if holder.is_some_and(|holder| holder < name) {
+            return Some(());
+        }
+
+        // The device holding the address lost it, it has to claim another one. Traffic heard
+        // from the address before an unknown device claimed it was that device's own.
+        let displaced = match self.devices.get(&source) {
+            Some(device) => device.name.is_some() || previous.is_some(),
+            None => false,
+        };
+        if displaced {
+            let device = self.devices.remove(&source)?;
+            events
+                .push(NetworkEvent::Left {
+                    address: source,
+                    name: device.name,
+                })
+                .ok();
+        }
+
+        match previous {
+            Some(address) => {
+                let mut device = self.devices.remove(&address)?;
+                device.last_seen = now;
+                self.devices.insert(source, device).ok()?;
+                events
+                    .push(NetworkEvent::AddressChanged {
+                        from: address,
+                        to: source,
+                        name,
+                    })
+                    .ok()
+            }
+            None => {
+                match self.devices.get_mut(&source) {
+                    // Device was heard before it claimed its address
+                    Some(device) => {
+                        device.name = Some(name);
+                        device.last_seen = now;
+                    }
+                    None => {
+                        self.devices
+                            .insert(source, Device::new(Some(name), now))
+                            .ok()?;
+                    }
+                }
+                events
+                    .push(NetworkEvent::Joined {
+                        address: source,
+                        name,
+                    })
+                    .ok()
+            }
+        }
+    }
+
+    /// Removes a device not heard from in more than `timeout` ms, call repeatedly until it
+    /// returns None
+    pub fn expire(&mut self, now: u64, timeout: u64) -> Option<NetworkEvent> {
+        let address = self
+            .devices
+            .iter()
+            .find(|(_, device)| now.saturating_sub(device.last_seen) > timeout)
+            .map(|(address, _)| *address)?;
+        let device = self.devices.remove(&address)?;
+        Some(NetworkEvent::Left {
+            address,
+            name: device.name,
+        })
+    }
+
+    pub fn device(&self, address: u8) -> Option<&Device> {
+        self.devices.get(&address)
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = (u8, &Device)> {
+        self.devices
+            .iter()
+            .map(|(address, device)| (*address, device))
+    }
+
+    /// Address claimed by the device with `name`
+    pub fn address_of(&self, name: Name) -> Option<u8> {
+        self.devices
+            .iter()
+            .find(|(_, device)| device.name == Some(name))
+            .map(|(address, _)| *address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Id, Message, Name, NetworkEvent, NetworkMap, Priority, GLOBAL_ADDRESS};
+
+    fn claim(source: u8, name: Name) -> (Id, [u8; 8]) {
+        (
+            Id::new(Priority::Priority6, 60928, source, GLOBAL_ADDRESS).unwrap(),
+            name.to_bytes(),
+        )
+    }
+
+    #[test]
+    fn network_map_process() {
+        let name = Name::from(0x80a0_2200_ff00_0064);
+        let other = Name::from(0x80a0_2200_ff00_0065);
+        let mut map = NetworkMap::<8>::new();
+
+        let (id, data) = claim(10, name);
+        assert_eq!(
+            [NetworkEvent::Joined { address: 10, name }],
+            &map.process(&Message::new(id, &data).unwrap(), 0)[..]
+        );
+        assert!(map
+            .process(&Message::new(id, &data).unwrap(), 10)
+            .is_empty());
+
+        let mut product = [0xffu8; 134];
+        product[4..8].copy_from_slice(b"Test");
+        let id = Id::new(Priority::Priority6, 126996, 10, GLOBAL_ADDRESS).unwrap();
+        assert!(map
+            .process(&Message::new(id, &product).unwrap(), 20)
+            .is_empty());
+        let device = map.device(10).unwrap();
+        assert_eq!("Test", device.product().unwrap().model());
+        assert_eq!(20, device.last_seen());
+        assert!(device.configuration().is_none());
+
+        let (id, data) = claim(11, name);
+        assert_eq!(
+            [NetworkEvent::AddressChanged {
+                from: 10,
+                to: 11,
+                name
+            }],
+            &map.process(&Message::new(id, &data).unwrap(), 30)[..]
+        );
+        assert!(map.device(10).is_none());
+        assert_eq!(Some(11), map.address_of(name));
+        assert_eq!("Test", map.device(11).unwrap().product().unwrap().model());
+
+        // Traffic from a device that has not claimed yet
+        let configuration = [4, 1, b'a', b'b', 2, 1, 2, 1];
+        let id = Id::new(Priority::Priority6, 126998, 20, GLOBAL_ADDRESS).unwrap();
+        assert!(map
+            .process(&Message::new(id, &configuration).unwrap(), 40)
+            .is_empty());
+        assert_eq!(None, map.device(20).unwrap().name());
+        let (id, data) = claim(20, other);
+        assert_eq!(
+            [NetworkEvent::Joined {
+                address: 20,
+                name: other
+            }],
+            &map.process(&Message::new(id, &data).unwrap(), 50)[..]
+        );
+        let configuration = map.device(20).unwrap().configuration().unwrap();
+        assert_eq!("ab", configuration.installation_description1());
+
+        // Cannot claim
+        let (id, data) = claim(254, name);
+        assert_eq!(
+            [NetworkEvent::Left {
+                address: 11,
+                name: Some(name)
+            }],
+            &map.process(&Message::new(id, &data).unwrap(), 60)[..]
+        );
+        assert_eq!(1, map.devices().count());
+    }
+
+    #[test]
+    fn network_map_displaced() {
+        let name = Name::from(0x80a0_2200_ff00_0064);
+        let other = Name::from(0x80a0_2200_ff00_0065);
+        let higher = Name::from(0x80a0_2200_ff00_0066);
+        let lower = Name::from(0x80a0_2200_ff00_0063);
+        let mut map = NetworkMap::<8>::new();
+        let (id, data) = claim(10, name);
+        map.process(&Message::new(id, &data).unwrap(), 0);
+        let (id, data) = claim(11, other);
+        map.process(&Message::new(id, &data).unwrap(), 0);
+
+        // A higher NAME loses the address to the device holding it
+        let (id, data) = claim(10, higher);
+        assert!(map.process(&Message::new(id, &data).unwrap(), 5).is_empty());
+        assert_eq!(Some(10), map.address_of(name));
+        assert_eq!(None, map.address_of(higher));
+
+        // A new device with a lower NAME wins the address of a known one
+        let (id, data) = claim(10, lower);
+        assert_eq!(
+            [
+                NetworkEvent::Left {
+                    address: 10,
+                    name: Some(name)
+                },
+                NetworkEvent::Joined {
+                    address: 10,
+                    name: lower
+                }
+            ],
+            &map.process(&Message::new(id, &data).unwrap(), 10)[..]
+        );
+        assert_eq!(None, map.address_of(name));
+
+        // A known device moves to the address of another one
+        let (id, data) = claim(11, lower);
+        assert_eq!(
+            [
+                NetworkEvent::Left {
+                    address: 11,
+                    name: Some(other)
+                },
+                NetworkEvent::AddressChanged {
+                    from: 10,
+                    to: 11,
+                    name: lower
+                }
+            ],
+            &map.process(&Message::new(id, &data).unwrap(), 20)[..]
+        );
+        assert_eq!(1, map.devices().count());
+        assert_eq!(Some(lower), map.device(11).unwrap().name());
+    }
+
+    #[test]
+    fn network_map_expire() {
+        let name = Name::from(0x80a0_2200_ff00_0064);
+        let mut map = NetworkMap::<8>::new();
+        let (id, data) = claim(10, name);
+        map.process(&Message::new(id, &data).unwrap(), 0);
+        let id = Id::new(Priority::Priority2, 127250, 12, GLOBAL_ADDRESS).unwrap();
+        map.process(&Message::new(id, &[0; 8]).unwrap(), 1000);
+
+        assert_eq!(None, map.expire(1000, 1000));
+        assert_eq!(
+            Some(NetworkEvent::Left {
+                address: 10,
+                name: Some(name)
+            }),
+            map.expire(1001, 1000)
+        );
+        assert_eq!(None, map.expire(1001, 1000));
+        assert!(map.device(12).is_some());
+    }
+}
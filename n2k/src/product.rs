@@ -1,6 +1,31 @@
+use crate::Responder;
+
+pub const PGN_PRODUCT_INFORMATION: u32 = 126996;
+
+/// Size of the 126996 Product Information payload
+pub const PRODUCT_INFORMATION_SIZE: usize = 134;
+/// Size of the fixed length strings of the payload
+pub const PRODUCT_STRING_SIZE: usize = 32;
+/// Highest valid certification level
+pub const MAX_CERTIFICATION_LEVEL: u8 = 2;
+/// Highest valid Load Equivalency Number, higher values are reserved or "not available"
+pub const MAX_LOAD_EQUIVALENCY: u8 = 252;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProductError {
+    InvalidLength,
+    InvalidString,
+    StringTooLong,
+    InvalidCertificationLevel,
+    InvalidLoadEquivalency,
+}
+
+pub type Result<T> = core::result::Result<T, ProductError>;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Product<'a> {
-    n2k: u8,
-    code: u8,
+    n2k: u16,
+    code: u16,
     model: &'a str,
     software: &'a str,
     version: &'a str,
@@ -10,20 +35,20 @@ pub struct Product<'a> {
 }
 
 impl<'a> Product<'a> {
+    /// Creates the product information of a device, the strings must be ASCII of at most 32
+    /// bytes and the load equivalency is given in units of 50 mA
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        n2k: u8,
-        code: u8,
+        n2k: u16,
+        code: u16,
         model: &'a str,
         software: &'a str,
         version: &'a str,
         serial: &'a str,
         certification: u8,
         load: u8,
-    ) -> Self {
-        //TODO: validate parameters
-
-        Product {
+    ) -> Result<Self> {
+        let product = Product {
             n2k,
             code,
             model,
@@ -32,14 +57,77 @@ impl<'a> Product<'a> {
             serial,
             certification,
             load,
+        };
+        product.validate()?;
+        Ok(product)
+    }
+
+    /// Checks the string lengths, certification level and load equivalency. Products decoded
+    /// with `from_bytes` are not validated.
+    pub fn validate(&self) -> Result<()> {
+        for string in [self.model, self.software, self.version, self.serial] {
+            if string.len() > PRODUCT_STRING_SIZE {
+                return Err(ProductError::StringTooLong);
+            }
+            if !string.is_ascii() {
+                return Err(ProductError::InvalidString);
+            }
+        }
+        if self.certification > MAX_CERTIFICATION_LEVEL {
+            return Err(ProductError::InvalidCertificationLevel);
+        }
+        if self.load > MAX_LOAD_EQUIVALENCY {
+            return Err(ProductError::InvalidLoadEquivalency);
+        }
+        Ok(())
+    }
+
+    /// Decodes a 126996 Product Information payload, the strings borrow from `data`
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self> {
+        if data.len() < PRODUCT_INFORMATION_SIZE {
+            return Err(ProductError::InvalidLength);
+        }
+
+        let string = |index: usize| {
+            fixed_string(&data[4 + index * PRODUCT_STRING_SIZE..][..PRODUCT_STRING_SIZE])
+        };
+        Ok(Product {
+            n2k: u16::from_le_bytes([data[0], data[1]]),
+            code: u16::from_le_bytes([data[2], data[3]]),
+            model: string(0)?,
+            software: string(1)?,
+            version: string(2)?,
+            serial: string(3)?,
+            certification: data[132],
+            load: data[133],
+        })
+    }
+
+    /// Writes the 126996 Product Information payload into `buf` and returns its length. The
+    /// strings are padded to 32 bytes with 0xff.
+    pub fn to_bytes(&self, buf: &mut [u8]) -> Result<usize> {
+        self.validate()?;
+        if buf.len() < PRODUCT_INFORMATION_SIZE {
+            return Err(ProductError::InvalidLength);
+        }
+
+        buf[0..2].copy_from_slice(&self.n2k.to_le_bytes());
+        buf[2..4].copy_from_slice(&self.code.to_le_bytes());
+        let strings = [self.model, self.software, self.version, self.serial];
+        for (field, string) in buf[4..].chunks_exact_mut(PRODUCT_STRING_SIZE).zip(&strings) {
+            field[..string.len()].copy_from_slice(string.as_bytes());
+            field[string.len()..].fill(0xff);
         }
+        buf[132] = self.certification;
+        buf[133] = self.load;
+        Ok(PRODUCT_INFORMATION_SIZE)
     }
 
-    pub fn n2k(&self) -> u8 {
+    pub fn n2k(&self) -> u16 {
         self.n2k
     }
 
-    pub fn code(&self) -> u8 {
+    pub fn code(&self) -> u16 {
         self.code
     }
 
@@ -67,3 +155,141 @@ impl<'a> Product<'a> {
         self.load
     }
 }
+
+impl<'a> Responder for Product<'a> {
+    fn respond(&self, pgn: u32, index: usize, buf: &mut [u8]) -> Option<usize> {
+        if pgn != PGN_PRODUCT_INFORMATION || index > 0 {
+            return None;
+        }
+        self.to_bytes(buf).ok()
+    }
+}
+
+/// Fixed length string padded with any of 0x00, 0xff, '@' or ' '
+fn fixed_string(data: &[u8]) -> Result<&str> {
+    let length = data
+        .iter()
+        .rposition(|c| !matches!(c, 0x00 | 0xff | b'@' | b' '))
+        .map_or(0, |i| i + 1);
+    core::str::from_utf8(&data[..length]).map_err(|_| ProductError::InvalidString)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::product::{ProductError, PRODUCT_INFORMATION_SIZE};
+    use crate::{Product, Responder};
+
+    #[test]
+    fn product_from_bytes() {
+        let mut data = [0xffu8; PRODUCT_INFORMATION_SIZE];
+        data[..4].copy_from_slice(&[0x34, 0x08, 0x39, 0x30]);
+        data[4..14].copy_from_slice(b"Test Model");
+        data[36..41].copy_from_slice(b"1.2.3");
+        data[68..71].copy_from_slice(b"Rev");
+        data[71..100].copy_from_slice(&[b'@'; 29]);
+        data[100..106].copy_from_slice(b"123456");
+        data[106..132].copy_from_slice(&[0; 26]);
+        data[132] = 1;
+        data[133] = 2;
+
+        let product = Product::from_bytes(&data).unwrap();
+        assert_eq!(2100, product.n2k());
+        assert_eq!(12345, product.code());
+        assert_eq!("Test Model", product.model());
+        assert_eq!("1.2.3", product.software());
+        assert_eq!("Rev", product.version());
+        assert_eq!("123456", product.serial());
+        assert_eq!(1, product.certification());
+        assert_eq!(2, product.load());
+
+        assert_eq!(
+            Err(ProductError::InvalidLength),
+            Product::from_bytes(&data[..PRODUCT_INFORMATION_SIZE - 1])
+        );
+        let mut buf = [0; PRODUCT_INFORMATION_SIZE + 1];
+        assert_eq!(
+            Some(PRODUCT_INFORMATION_SIZE),
+            product.respond(126996, 0, &mut buf)
+        );
+        assert_eq!(Ok(product), Product::from_bytes(&buf));
+        assert_eq!(None, product.respond(126996, 1, &mut buf));
+        assert_eq!(None, product.respond(126998, 0, &mut buf));
+
+        data[4] = 0xc3;
+        assert_eq!(Err(ProductError::InvalidString), Product::from_bytes(&data));
+    }
+
+    #[test]
+    fn product_new() {
+        struct TestCase<'a> {
+            model: &'a str,
+            certification: u8,
+            load: u8,
+            result: Result<(), ProductError>,
+        }
+        let test_cases = [
+            TestCase {
+                model: "Model",
+                certification: 2,
+                load: 252,
+                result: Ok(()),
+            },
+            TestCase {
+                model: "0123456789abcdef0123456789abcdef",
+                certification: 0,
+                load: 0,
+                result: Ok(()),
+            },
+            TestCase {
+                model: "0123456789abcdef0123456789abcdef0",
+                certification: 1,
+                load: 1,
+                result: Err(ProductError::StringTooLong),
+            },
+            TestCase {
+                model: "Modèle",
+                certification: 1,
+                load: 1,
+                result: Err(ProductError::InvalidString),
+            },
+            TestCase {
+                model: "Model",
+                certification: 3,
+                load: 1,
+                result: Err(ProductError::InvalidCertificationLevel),
+            },
+            TestCase {
+                model: "Model",
+                certification: 1,
+                load: 0xff,
+                result: Err(ProductError::InvalidLoadEquivalency),
+            },
+        ];
+        for i in &test_cases {
+            let product = Product::new(2100, 1, i.model, "1.0", "A", "1", i.certification, i.load);
+            assert_eq!(i.result, product.map(|_| ()));
+        }
+    }
+
+    #[test]
+    fn product_to_bytes() {
+        let product = Product::new(2100, 12345, "Model", "1.0", "", "SN1", 1, 2).unwrap();
+        let mut buf = [0; PRODUCT_INFORMATION_SIZE];
+        assert_eq!(Ok(PRODUCT_INFORMATION_SIZE), product.to_bytes(&mut buf));
+        assert_eq!(
+            buf[..9],
+            [0x34, 0x08, 0x39, 0x30, b'M', b'o', b'd', b'e', b'l']
+        );
+        assert!(buf[9..36].iter().all(|c| *c == 0xff));
+        assert_eq!(buf[36..39], *b"1.0");
+        assert!(buf[68..100].iter().all(|c| *c == 0xff));
+        assert_eq!(buf[100..103], *b"SN1");
+        assert_eq!(buf[132..], [1, 2]);
+        assert_eq!(Ok(product), Product::from_bytes(&buf));
+
+        assert_eq!(
+            Err(ProductError::InvalidLength),
+            product.to_bytes(&mut buf[..PRODUCT_INFORMATION_SIZE - 1])
+        );
+    }
+}
@@ -0,0 +1,182 @@
+pub(crate) const PGN_ISO_REQUEST: u32 = 59904;
+pub(crate) const PGN_ISO_ACKNOWLEDGEMENT: u32 = 59392;
+pub(crate) const PGN_PGN_LIST: u32 = 126464;
+
+/// Control byte of an ISO Acknowledgement
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AcknowledgementControl {
+    Ack,
+    Nak,
+    AccessDenied,
+    CannotRespond,
+}
+
+impl From<AcknowledgementControl> for u8 {
+    fn from(control: AcknowledgementControl) -> Self {
+        match control {
+            AcknowledgementControl::Ack => 0,
+            AcknowledgementControl::Nak => 1,
+            AcknowledgementControl::AccessDenied => 2,
+            AcknowledgementControl::CannotRespond => 3,
+        }
+    }
+}
+
+/// Payload of an ISO Acknowledgement of `pgn` sent to global, `address` is the acknowledged
+/// requester
+pub(crate) fn acknowledgement(control: AcknowledgementControl, address: u8, pgn: u32) -> [u8; 8] {
+    [
+        control.into(),
+        0xff, // group function
+        0xff,
+        0xff,
+        address,
+        (pgn & 0xff) as u8,
+        ((pgn >> 8) & 0xff) as u8,
+        ((pgn >> 16) & 0xff) as u8,
+    ]
+}
+
+/// Answers ISO Requests for PGNs, see `Bus::with_responder`
+pub trait Responder {
+    /// Writes the `index`th response to a request for `pgn` into `buf` and returns its length.
+    /// Returns None once every response was written, right away if `pgn` is not supported.
+    fn respond(&self, pgn: u32, index: usize, buf: &mut [u8]) -> Option<usize>;
+}
+
+/// Responds to nothing, every addressed request is NAKed
+impl Responder for () {
+    fn respond(&self, _pgn: u32, _index: usize, _buf: &mut [u8]) -> Option<usize> {
+        None
+    }
+}
+
+impl<R: Responder + ?Sized> Responder for &R {
+    fn respond(&self, pgn: u32, index: usize, buf: &mut [u8]) -> Option<usize> {
+        (**self).respond(pgn, index, buf)
+    }
+}
+
+macro_rules! impl_responder_tuple {
+    ($($r:ident),+) => {
+        /// Asks every responder in order, the first one supporting the PGN writes every
+        /// response
+        impl<$($r: Responder),+> Responder for ($($r,)+) {
+            #[allow(non_snake_case)]
+            fn respond(&self, pgn: u32, index: usize, buf: &mut [u8]) -> Option<usize> {
+                let ($($r,)+) = self;
+                // The responder is the one writing the first response
+                $(
+                    if let Some(length) = $r.respond(pgn, 0, buf) {
+                        return if index == 0 {
+                            Some(length)
+                        } else {
+                            $r.respond(pgn, index, buf)
+                        };
+                    }
+                )+
+                None
+            }
+        }
+    };
+}
+
+impl_responder_tuple!(A);
+impl_responder_tuple!(A, B);
+impl_responder_tuple!(A, B, C);
+impl_responder_tuple!(A, B, C, D);
+impl_responder_tuple!(A, B, C, D, E);
+
+/// Answers requests for 126464 PGN List with the PGNs we transmit and receive
+#[derive(Copy, Clone, Debug)]
+pub struct PgnList<'a> {
+    transmit: &'a [u32],
+    receive: &'a [u32],
+}
+
+impl<'a> PgnList<'a> {
+    pub fn new(transmit: &'a [u32], receive: &'a [u32]) -> Self {
+        PgnList { transmit, receive }
+    }
+}
+
+impl<'a> Responder for PgnList<'a> {
+    fn respond(&self, pgn: u32, index: usize, buf: &mut [u8]) -> Option<usize> {
+        let (function, pgns) = match (pgn, index) {
+            (PGN_PGN_LIST, 0) => (0, self.transmit),
+            (PGN_PGN_LIST, 1) => (1, self.receive),
+            _ => return None,
+        };
+
+        // PGNs that do not fit are left out
+        let count = pgns.len().min((buf.len() - 1) / 3);
+        buf[0] = function;
+        for (chunk, pgn) in buf[1..].chunks_exact_mut(3).zip(&pgns[..count]) {
+            chunk.copy_from_slice(&pgn.to_le_bytes()[..3]);
+        }
+        Some(1 + count * 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::request::{acknowledgement, AcknowledgementControl};
+    use crate::{PgnList, Responder};
+
+    #[test]
+    fn request_pgn_list() {
+        let list = PgnList::new(&[59392, 60928, 126996], &[59904]);
+        let mut buf = [0; 16];
+        assert_eq!(Some(10), list.respond(126464, 0, &mut buf));
+        assert_eq!(
+            buf[..10],
+            [0, 0x00, 0xe8, 0x00, 0x00, 0xee, 0x00, 0x14, 0xf0, 0x01]
+        );
+        assert_eq!(Some(4), list.respond(126464, 1, &mut buf));
+        assert_eq!(buf[..4], [1, 0x00, 0xea, 0x00]);
+        assert_eq!(None, list.respond(126464, 2, &mut buf));
+        assert_eq!(None, list.respond(126996, 0, &mut buf));
+
+        // The first responder supporting the PGN answers
+        let responders = ((), list, PgnList::new(&[], &[]));
+        assert_eq!(Some(10), responders.respond(126464, 0, &mut buf));
+        assert_eq!(Some(4), responders.respond(126464, 1, &mut buf));
+    }
+
+    #[test]
+    fn request_responder_tuple() {
+        // Answers 126996 with its first field, as many times as its second field
+        struct Responses(u8, usize);
+        impl Responder for Responses {
+            fn respond(&self, pgn: u32, index: usize, buf: &mut [u8]) -> Option<usize> {
+                if pgn != 126996 || index >= self.1 {
+                    return None;
+                }
+                buf[0] = self.0;
+                Some(1)
+            }
+        }
+
+        // Responses are never mixed from several responders
+        let mut buf = [0; 8];
+        let responders = (Responses(1, 1), Responses(2, 3));
+        assert_eq!(Some(1), responders.respond(126996, 0, &mut buf));
+        assert_eq!(1, buf[0]);
+        assert_eq!(None, responders.respond(126996, 1, &mut buf));
+
+        let responders = (Responses(1, 0), Responses(2, 3));
+        for index in 0..3 {
+            assert_eq!(Some(1), responders.respond(126996, index, &mut buf));
+            assert_eq!(2, buf[0]);
+        }
+        assert_eq!(None, responders.respond(126996, 3, &mut buf));
+    }
+
+    #[test]
+    fn request_acknowledgement() {
+        assert_eq!(
+            acknowledgement(AcknowledgementControl::Nak, 12, 126998),
+            [1, 0xff, 0xff, 0xff, 12, 0x16, 0xf0, 0x01]
+        );
+    }
+}
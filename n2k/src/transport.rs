@@ -0,0 +1,449 @@
+use crate::Id;
+
+pub const PGN_TP_CM: u32 = 0x00ec00; // 60416 - ISO Transport Protocol, Connection Management - RTS group
+pub const PGN_TP_DT: u32 = 0x00eb00; // 60160 - ISO Transport Protocol, Data Transfer
+
+pub const CB_TP_RTS: u8 = 0x10; // Control byte indicating TP_RTS
+pub const CB_TP_CTS: u8 = 0x11; // Control byte indicating TP_CTS
+pub const CB_TP_EOMA: u8 = 0x13; // Control byte indicating TP_EndOfMsgAck
+pub const CB_TP_BAM: u8 = 0x20; // Control byte indicating TP_BAM
+pub const CB_TP_ABORT: u8 = 0xff; // Control byte indicating TP_Conn_Abort
+
+/// Maximum payload of a transport protocol message: 255 packets of 7 bytes
+pub const MAX_TRANSPORT_SIZE: usize = 1785;
+
+// J1939-21 timeouts in milliseconds
+pub const T1: u64 = 750; // receiver, between two data packets
+pub const T2: u64 = 1250; // receiver, after sending a CTS
+pub const T3: u64 = 1250; // sender, after sending an RTS or the last packet of a window
+pub const T4: u64 = 1050; // sender, after receiving a CTS hold
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TransportError {
+    InvalidAnnouncement {
+        size: usize,
+        packets: usize,
+    },
+    UnexpectedSequenceNumber {
+        sequence: u8,
+        expected: u8,
+    },
+    /// A connection mode transfer is already in progress
+    Busy,
+    Timeout,
+    Aborted(AbortReason),
+}
+
+/// Reason given in a TP.Conn_Abort
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AbortReason {
+    AlreadyInSession,
+    ResourcesNeeded,
+    Timeout,
+    ClearToSendWhileSending,
+    RetransmitLimit,
+    UnexpectedDataTransfer,
+    BadSequenceNumber,
+    Other(u8),
+}
+
+impl From<u8> for AbortReason {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => AbortReason::AlreadyInSession,
+            2 => AbortReason::ResourcesNeeded,
+            3 => AbortReason::Timeout,
+            4 => AbortReason::ClearToSendWhileSending,
+            5 => AbortReason::RetransmitLimit,
+            6 => AbortReason::UnexpectedDataTransfer,
+            7 => AbortReason::BadSequenceNumber,
+            v => AbortReason::Other(v),
+        }
+    }
+}
+
+impl From<AbortReason> for u8 {
+    fn from(reason: AbortReason) -> Self {
+        match reason {
+            AbortReason::AlreadyInSession => 1,
+            AbortReason::ResourcesNeeded => 2,
+            AbortReason::Timeout => 3,
+            AbortReason::ClearToSendWhileSending => 4,
+            AbortReason::RetransmitLimit => 5,
+            AbortReason::UnexpectedDataTransfer => 6,
+            AbortReason::BadSequenceNumber => 7,
+            AbortReason::Other(v) => v,
+        }
+    }
+}
+
+/// Decoded TP.CM frame
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConnectionManagement {
+    RequestToSend {
+        size: usize,
+        packets: u8,
+        max_packets: u8,
+        pgn: u32,
+    },
+    ClearToSend {
+        packets: u8,
+        next_packet: u8,
+        pgn: u32,
+    },
+    EndOfMessageAck {
+        size: usize,
+        packets: u8,
+        pgn: u32,
+    },
+    Abort {
+        reason: AbortReason,
+        pgn: u32,
+    },
+    BroadcastAnnounce {
+        size: usize,
+        packets: u8,
+        pgn: u32,
+    },
+}
+
+impl ConnectionManagement {
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        let size = data[1] as usize | (data[2] as usize) << 8;
+        let pgn = data[5] as u32 | (data[6] as u32) << 8 | (data[7] as u32) << 16;
+
+        match data[0] {
+            CB_TP_RTS => Some(ConnectionManagement::RequestToSend {
+                size,
+                packets: data[3],
+                max_packets: data[4],
+                pgn,
+            }),
+            CB_TP_CTS => Some(ConnectionManagement::ClearToSend {
+                packets: data[1],
+                next_packet: data[2],
+                pgn,
+            }),
+            CB_TP_EOMA => Some(ConnectionManagement::EndOfMessageAck {
+                size,
+                packets: data[3],
+                pgn,
+            }),
+            CB_TP_ABORT => Some(ConnectionManagement::Abort {
+                reason: data[1].into(),
+                pgn,
+            }),
+            CB_TP_BAM => Some(ConnectionManagement::BroadcastAnnounce {
+                size,
+                packets: data[3],
+                pgn,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self) -> [u8; 8] {
+        let (header, pgn) = match *self {
+            ConnectionManagement::RequestToSend {
+                size,
+                packets,
+                max_packets,
+                pgn,
+            } => (
+                [
+                    CB_TP_RTS,
+                    size as u8,
+                    (size >> 8) as u8,
+                    packets,
+                    max_packets,
+                ],
+                pgn,
+            ),
+            ConnectionManagement::ClearToSend {
+                packets,
+                next_packet,
+                pgn,
+            } => ([CB_TP_CTS, packets, next_packet, 0xff, 0xff], pgn),
+            ConnectionManagement::EndOfMessageAck { size, packets, pgn } => (
+                [CB_TP_EOMA, size as u8, (size >> 8) as u8, packets, 0xff],
+                pgn,
+            ),
+            ConnectionManagement::Abort { reason, pgn } => {
+                ([CB_TP_ABORT, reason.into(), 0xff, 0xff, 0xff], pgn)
+            }
+            ConnectionManagement::BroadcastAnnounce { size, packets, pgn } => (
+                [CB_TP_BAM, size as u8, (size >> 8) as u8, packets, 0xff],
+                pgn,
+            ),
+        };
+
+        [
+            header[0],
+            header[1],
+            header[2],
+            header[3],
+            header[4],
+            (pgn & 0xff) as u8,         // PGN LSB
+            ((pgn >> 8) & 0xff) as u8,  // PGN
+            ((pgn >> 16) & 0xff) as u8, // PGN MSB
+        ]
+    }
+}
+
+/// Identifier for transport protocol sessions that are being reassembled: (source, PGN)
+pub type TransportIdentifier = (u8, u32);
+pub struct TransportSession {
+    pub data: heapless::Vec<u8, MAX_TRANSPORT_SIZE>,
+    pub total_size: usize,
+    pub total_packets: u8,
+    pub last_sequence: u8,
+    /// Last sequence number of the current CTS window, `None` for broadcasts
+    pub window_end: Option<u8>,
+    /// Maximum number of packets per CTS window accepted by the sender
+    pub max_packets: u8,
+    /// Time at which the session is abandoned if no further packet is received
+    pub deadline: u64,
+    /// Order in which the session was opened
+    pub serial: u32,
+}
+
+impl TransportSession {
+    pub fn new(total_size: usize, total_packets: u8) -> Result<Self, TransportError> {
+        // Multi-packet messages carry more than a single frame, in exactly as many packets as needed
+        if total_size <= 8
+            || total_size > MAX_TRANSPORT_SIZE
+            || total_packets as usize != total_size.div_ceil(7)
+        {
+            return Err(TransportError::InvalidAnnouncement {
+                size: total_size,
+                packets: total_packets as usize,
+            });
+        }
+
+        Ok(Self {
+            data: heapless::Vec::new(),
+            total_size,
+            total_packets,
+            last_sequence: 0,
+            window_end: None,
+            max_packets: 0xff,
+            deadline: 0,
+            serial: 0,
+        })
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.window_end.is_none()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.data.len() >= self.total_size
+    }
+
+    pub fn complete_data(&self) -> Option<&[u8]> {
+        if self.is_complete() {
+            Some(&self.data[..self.total_size])
+        } else {
+            None
+        }
+    }
+
+    /// Opens the next CTS window, returning the number of packets it spans
+    pub fn next_window(&mut self) -> u8 {
+        let remaining = self.total_packets - self.last_sequence;
+        let packets = remaining.min(self.max_packets);
+        self.window_end = Some(self.last_sequence + packets);
+        packets
+    }
+
+    pub fn extend(&mut self, sequence: u8, data: &[u8]) -> Result<bool, TransportError> {
+        // Sequence numbers start at 1
+        let last = self.window_end.unwrap_or(self.total_packets);
+        if sequence != self.last_sequence.wrapping_add(1) || sequence > last {
+            return Err(TransportError::UnexpectedSequenceNumber {
+                sequence,
+                expected: self.last_sequence.wrapping_add(1),
+            });
+        }
+
+        // The last packet is padded, only keep what fits
+        let len = data.len().min(self.data.capacity() - self.data.len());
+        self.data.extend_from_slice(&data[..len]).unwrap();
+        self.last_sequence = sequence;
+
+        Ok(self.is_complete())
+    }
+}
+
+/// Outgoing connection mode transfer
+pub struct SendSession {
+    /// Identifier of the transferred message, including its destination
+    pub id: Id,
+    pub data: heapless::Vec<u8, MAX_TRANSPORT_SIZE>,
+    /// Next packet to send
+    pub next_packet: usize,
+    /// Last packet of the window granted by the receiver's CTS, 0 while waiting for one
+    pub window_end: usize,
+    /// Time at which the transfer is aborted, `None` while packets are pending
+    pub deadline: Option<u64>,
+}
+
+impl SendSession {
+    pub fn new(id: Id, data: &[u8], now: u64) -> Result<Self, TransportError> {
+        let mut session = Self {
+            id,
+            data: heapless::Vec::new(),
+            next_packet: 1,
+            window_end: 0,
+            deadline: Some(now + T3),
+        };
+        session
+            .data
+            .extend_from_slice(data)
+            .map_err(|_| TransportError::InvalidAnnouncement {
+                size: data.len(),
+                packets: data.len().div_ceil(7),
+            })?;
+
+        Ok(session)
+    }
+
+    pub fn total_packets(&self) -> usize {
+        self.data.len().div_ceil(7)
+    }
+
+    pub fn request_to_send(&self) -> ConnectionManagement {
+        ConnectionManagement::RequestToSend {
+            size: self.data.len(),
+            packets: self.total_packets() as u8,
+            max_packets: 0xff,
+            pgn: self.id.pgn(),
+        }
+    }
+
+    /// Payload of the data packet with the given sequence number, padded to 8 bytes
+    pub fn packet(&self, sequence: usize) -> [u8; 8] {
+        let mut packet = [0xff; 8];
+        packet[0] = sequence as u8;
+        let start = (sequence - 1) * 7;
+        let end = (start + 7).min(self.data.len());
+        packet[1..1 + end - start].copy_from_slice(&self.data[start..end]);
+        packet
+    }
+
+    pub fn has_pending_packets(&self) -> bool {
+        self.next_packet <= self.window_end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Priority, GLOBAL_ADDRESS};
+
+    #[test]
+    fn connection_management_decode() {
+        let data = [0x20, 0x14, 0x00, 0x03, 0xff, 0x00, 0xee, 0x01];
+
+        assert_eq!(
+            ConnectionManagement::decode(&data),
+            Some(ConnectionManagement::BroadcastAnnounce {
+                size: 20,
+                packets: 3,
+                pgn: 126464,
+            })
+        );
+        assert_eq!(ConnectionManagement::decode(&data[..7]), None);
+    }
+
+    #[test]
+    fn connection_management_roundtrip() {
+        let test_cases = [
+            ConnectionManagement::RequestToSend {
+                size: 1785,
+                packets: 255,
+                max_packets: 16,
+                pgn: 126998,
+            },
+            ConnectionManagement::ClearToSend {
+                packets: 4,
+                next_packet: 9,
+                pgn: 126998,
+            },
+            ConnectionManagement::EndOfMessageAck {
+                size: 300,
+                packets: 43,
+                pgn: 65240,
+            },
+            ConnectionManagement::Abort {
+                reason: AbortReason::Timeout,
+                pgn: 126464,
+            },
+            ConnectionManagement::BroadcastAnnounce {
+                size: 9,
+                packets: 2,
+                pgn: 65280,
+            },
+        ];
+        for i in &test_cases {
+            assert_eq!(ConnectionManagement::decode(&i.encode()), Some(*i));
+        }
+    }
+
+    #[test]
+    fn transport_session_new() {
+        assert!(TransportSession::new(9, 2).is_ok());
+        assert!(TransportSession::new(MAX_TRANSPORT_SIZE, 255).is_ok());
+        assert!(TransportSession::new(8, 2).is_err());
+        assert!(TransportSession::new(20, 4).is_err());
+        assert!(TransportSession::new(MAX_TRANSPORT_SIZE + 1, 255).is_err());
+    }
+
+    #[test]
+    fn transport_session_extend() {
+        let mut session = TransportSession::new(10, 2).unwrap();
+
+        assert!(!session.extend(1, &[1, 2, 3, 4, 5, 6, 7]).unwrap());
+        assert!(session
+            .extend(3, &[8, 9, 10, 0xff, 0xff, 0xff, 0xff])
+            .is_err());
+        assert!(session
+            .extend(2, &[8, 9, 10, 0xff, 0xff, 0xff, 0xff])
+            .unwrap());
+        assert_eq!(
+            session.complete_data(),
+            Some(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10][..])
+        );
+    }
+
+    #[test]
+    fn transport_session_window() {
+        let mut session = TransportSession::new(30, 5).unwrap();
+        session.max_packets = 2;
+
+        assert_eq!(session.next_window(), 2);
+        session.extend(1, &[0; 7]).unwrap();
+        session.extend(2, &[0; 7]).unwrap();
+        // Packets beyond the window are rejected
+        assert!(session.extend(3, &[0; 7]).is_err());
+        assert_eq!(session.next_window(), 2);
+        assert_eq!(session.window_end, Some(4));
+        session.extend(3, &[0; 7]).unwrap();
+        session.extend(4, &[0; 7]).unwrap();
+        assert_eq!(session.next_window(), 1);
+    }
+
+    #[test]
+    fn send_session_packets() {
+        let data: heapless::Vec<u8, 16> = (0..16).collect();
+        let id = Id::new(Priority::Priority6, 126998, 0, GLOBAL_ADDRESS).unwrap();
+        let session = SendSession::new(id, &data, 0).unwrap();
+
+        assert_eq!(session.total_packets(), 3);
+        assert_eq!(session.packet(1), [1, 0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(session.packet(3), [3, 14, 15, 0xff, 0xff, 0xff, 0xff, 0xff]);
+    }
+}